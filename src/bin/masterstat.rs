@@ -0,0 +1,323 @@
+use std::collections::BTreeMap;
+use std::process::ExitCode;
+use std::time::Duration;
+
+use masterstat::{Game, MasterResults, ParseGameError, ServerAddress};
+
+const USAGE: &str = "\
+Get server addresses from master servers
+
+Usage: masterstat [OPTIONS] [MASTER]...
+
+Arguments:
+  [MASTER]...  Master server addresses (host:port), known masters of the protocol if none given
+
+Options:
+  -p, --protocol <PROTOCOL>  quakeworld, quake2, quake3, xonotic or halflife [default: quakeworld]
+  -t, --timeout <MS>         Time to wait for each master in milliseconds [default: 2000]
+  -f, --format <FORMAT>      plain, json, ndjson or csv [default: plain]
+  -h, --help                 Print help";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Format {
+    Plain,
+    Json,
+    Ndjson,
+    Csv,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Args {
+    masters: Vec<String>,
+    game: Game,
+    timeout: Duration,
+    format: Format,
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(message) => {
+            eprintln!("error: {}\n\n{}", message, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let masters = match args.masters.is_empty() {
+        true => masterstat::known_masters(args.game)
+            .iter()
+            .map(|m| m.to_string())
+            .collect(),
+        false => args.masters,
+    };
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("failed to start runtime");
+    let results = runtime.block_on(masterstat::master_results_with(
+        &masters,
+        &*args.game.protocol(),
+        Some(args.timeout),
+    ));
+
+    for result in results.errors() {
+        if let Err(e) = &result.outcome {
+            eprintln!("{}: {}", result.master_address, e);
+        }
+    }
+
+    print!("{}", format_output(&results, args.format));
+
+    match results.results.iter().any(|r| r.is_ok()) {
+        true => ExitCode::SUCCESS,
+        false => ExitCode::FAILURE,
+    }
+}
+
+/// Parse command-line arguments, `None` if help was requested
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Option<Args>, String> {
+    let mut parsed = Args {
+        masters: vec![],
+        game: Game::QuakeWorld,
+        timeout: Duration::from_secs(2),
+        format: Format::Plain,
+    };
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        let (name, inline_value) = match arg.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => (name.to_string(), Some(value)),
+            _ => (arg.clone(), None),
+        };
+        let mut value = || {
+            inline_value
+                .map(str::to_string)
+                .or_else(|| args.next())
+                .ok_or_else(|| format!("missing value for {}", name))
+        };
+
+        match name.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "-p" | "--protocol" => {
+                parsed.game = value()?
+                    .parse()
+                    .map_err(|e: ParseGameError| e.to_string())?;
+            }
+            "-t" | "--timeout" => {
+                let value = value()?;
+                let millis = value
+                    .parse()
+                    .map_err(|_| format!("invalid timeout: {}", value))?;
+                parsed.timeout = Duration::from_millis(millis);
+            }
+            "-f" | "--format" => {
+                parsed.format = match value()?.as_str() {
+                    "plain" => Format::Plain,
+                    "json" => Format::Json,
+                    "ndjson" => Format::Ndjson,
+                    "csv" => Format::Csv,
+                    other => return Err(format!("unknown format: {}", other)),
+                };
+            }
+            option if option.starts_with('-') => {
+                return Err(format!("unknown option: {}", option));
+            }
+            _ => parsed.masters.push(arg),
+        }
+    }
+
+    Ok(Some(parsed))
+}
+
+/// Server addresses (sorted and unique) and the masters listing each of them
+fn provenance(results: &MasterResults) -> BTreeMap<&ServerAddress, Vec<&str>> {
+    let mut servers = BTreeMap::<&ServerAddress, Vec<&str>>::new();
+
+    for result in &results.results {
+        for server in result.outcome.iter().flatten() {
+            let masters = servers.entry(server).or_default();
+            if !masters.contains(&result.master_address.as_str()) {
+                masters.push(&result.master_address);
+            }
+        }
+    }
+
+    servers
+}
+
+fn format_output(results: &MasterResults, format: Format) -> String {
+    let servers = provenance(results);
+    let json_object = |server: &ServerAddress, masters: &[&str]| {
+        let masters = masters
+            .iter()
+            .map(|m| json_string(m))
+            .collect::<Vec<String>>()
+            .join(",");
+        format!(
+            "{{\"address\":{},\"ip\":{},\"port\":{},\"masters\":[{}]}}",
+            json_string(&server.to_string()),
+            json_string(&server.ip.to_string()),
+            server.port,
+            masters
+        )
+    };
+
+    match format {
+        Format::Plain => servers.keys().map(|s| format!("{}\n", s)).collect(),
+        Format::Json => {
+            let objects = servers
+                .iter()
+                .map(|(server, masters)| json_object(server, masters))
+                .collect::<Vec<String>>();
+            format!("[{}]\n", objects.join(","))
+        }
+        Format::Ndjson => servers
+            .iter()
+            .map(|(server, masters)| format!("{}\n", json_object(server, masters)))
+            .collect(),
+        Format::Csv => {
+            let rows = servers.iter().flat_map(|(server, masters)| {
+                masters.iter().map(move |master| {
+                    format!(
+                        "{},{},{}\n",
+                        csv_field(&server.ip.to_string()),
+                        server.port,
+                        csv_field(master)
+                    )
+                })
+            });
+            std::iter::once("ip,port,master\n".to_string())
+                .chain(rows)
+                .collect()
+        }
+    }
+}
+
+fn json_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+fn csv_field(value: &str) -> String {
+    match value.contains([',', '"', '\n']) {
+        true => format!("\"{}\"", value.replace('"', "\"\"")),
+        false => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use masterstat::{Error, MasterResult};
+
+    use super::*;
+
+    fn args(args: &[&str]) -> Result<Option<Args>, String> {
+        parse_args(args.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn test_parse_args() {
+        // defaults
+        assert_eq!(
+            args(&[]),
+            Ok(Some(Args {
+                masters: vec![],
+                game: Game::QuakeWorld,
+                timeout: Duration::from_secs(2),
+                format: Format::Plain,
+            }))
+        );
+
+        // options
+        assert_eq!(
+            args(&[
+                "-p",
+                "quake3",
+                "--timeout=500",
+                "--format",
+                "csv",
+                "a:1",
+                "b:2"
+            ]),
+            Ok(Some(Args {
+                masters: vec!["a:1".to_string(), "b:2".to_string()],
+                game: Game::Quake3,
+                timeout: Duration::from_millis(500),
+                format: Format::Csv,
+            }))
+        );
+
+        // help
+        assert_eq!(args(&["a:1", "--help"]), Ok(None));
+
+        // errors
+        assert_eq!(args(&["-f", "xml"]), Err("unknown format: xml".to_string()));
+        assert_eq!(args(&["-t"]), Err("missing value for -t".to_string()));
+        assert_eq!(args(&["-x"]), Err("unknown option: -x".to_string()));
+    }
+
+    #[test]
+    fn test_format_output() {
+        let server1 = ServerAddress::new("10.0.0.1".parse().unwrap(), 27500);
+        let server2 = ServerAddress::new("10.0.0.2".parse().unwrap(), 28000);
+        let result = |master: &str, outcome| MasterResult {
+            master_address: master.to_string(),
+            socket_address: None,
+            outcome,
+            rtt: None,
+            packet_count: 0,
+            attempts: 1,
+            complete: true,
+        };
+        let results = MasterResults {
+            results: vec![
+                result("a:1", Ok(vec![server1.clone(), server2.clone()])),
+                result("b:2", Ok(vec![server1.clone()])),
+                result("c:3", Err(Error::Timeout)),
+            ],
+        };
+
+        assert_eq!(
+            format_output(&results, Format::Plain),
+            "10.0.0.1:27500\n10.0.0.2:28000\n"
+        );
+        assert_eq!(
+            format_output(&results, Format::Ndjson),
+            "{\"address\":\"10.0.0.1:27500\",\"ip\":\"10.0.0.1\",\"port\":27500,\"masters\":[\"a:1\",\"b:2\"]}\n\
+             {\"address\":\"10.0.0.2:28000\",\"ip\":\"10.0.0.2\",\"port\":28000,\"masters\":[\"a:1\"]}\n"
+        );
+        assert_eq!(
+            format_output(&results, Format::Json),
+            "[{\"address\":\"10.0.0.1:27500\",\"ip\":\"10.0.0.1\",\"port\":27500,\"masters\":[\"a:1\",\"b:2\"]},\
+             {\"address\":\"10.0.0.2:28000\",\"ip\":\"10.0.0.2\",\"port\":28000,\"masters\":[\"a:1\"]}]\n"
+        );
+        assert_eq!(
+            format_output(&results, Format::Csv),
+            "ip,port,master\n10.0.0.1,27500,a:1\n10.0.0.1,27500,b:2\n10.0.0.2,28000,a:1\n"
+        );
+    }
+
+    #[test]
+    fn test_escaping() {
+        assert_eq!(json_string("a\"b\\c\n"), "\"a\\\"b\\\\c\\u000a\"");
+        assert_eq!(csv_field("a,\"b\""), "\"a,\"\"b\"\"\"");
+        assert_eq!(csv_field("a:1"), "a:1");
+    }
+}
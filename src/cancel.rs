@@ -0,0 +1,182 @@
+use std::future::Future;
+use std::pin::pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use futures::future::Either;
+use tokio::sync::Notify;
+
+use crate::error::Error;
+
+/// Token for cancelling queries from another task
+///
+/// Clones share the same state, cancelling one cancels all of them.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use masterstat::{CancellationToken, QueryOptions, QuakeWorld};
+///
+/// async fn test() {
+///     let token = CancellationToken::new();
+///     let options = QueryOptions::new().cancellation_token(token.clone());
+///
+///     tokio::spawn(async move {
+///         tokio::time::sleep(Duration::from_secs(1)).await;
+///         token.cancel();
+///     });
+///
+///     let masters = ["master.quakeworld.nu:27000", "master.quakeservers.net:27000"];
+///     let results = masterstat::master_results_with_options(&masters, &QuakeWorld, &options).await;
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    is_cancelled: AtomicBool,
+    notify: Notify,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.inner.is_cancelled.store(true, Ordering::SeqCst);
+        self.inner.notify.notify_waiters();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.is_cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Wait until the token is cancelled
+    pub async fn cancelled(&self) {
+        let mut notified = pin!(self.inner.notify.notified());
+        notified.as_mut().enable();
+
+        if !self.is_cancelled() {
+            notified.await;
+        }
+    }
+}
+
+/// Deadline and cancellation token shared by all queries of a call
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Stop {
+    deadline: Option<Instant>,
+    token: Option<CancellationToken>,
+}
+
+impl Stop {
+    /// Stop after `deadline` from now or once the token is cancelled
+    pub fn new(deadline: Option<Duration>, token: Option<CancellationToken>) -> Self {
+        Stop {
+            deadline: deadline.map(|d| Instant::now() + d),
+            token,
+        }
+    }
+
+    /// Time until the deadline, `None` without a deadline
+    pub fn remaining(&self) -> Option<Duration> {
+        self.deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+
+    /// Timeout limited to the time until the deadline
+    pub fn limit(&self, timeout: Option<Duration>) -> Option<Duration> {
+        match (timeout, self.remaining()) {
+            (Some(timeout), Some(remaining)) => Some(timeout.min(remaining)),
+            (timeout, remaining) => timeout.or(remaining),
+        }
+    }
+
+    /// Reason for stopping, if reached
+    pub fn reason(&self) -> Option<Error> {
+        if self.token.as_ref().is_some_and(|t| t.is_cancelled()) {
+            return Some(Error::Cancelled);
+        }
+        match self.deadline {
+            Some(deadline) if Instant::now() >= deadline => Some(Error::Timeout),
+            _ => None,
+        }
+    }
+
+    /// Wait until the deadline passes or the token is cancelled
+    async fn reached(&self) -> Error {
+        let deadline = async {
+            match self.deadline {
+                Some(deadline) => tokio::time::sleep_until(deadline.into()).await,
+                None => std::future::pending().await,
+            }
+        };
+        let cancelled = async {
+            match &self.token {
+                Some(token) => token.cancelled().await,
+                None => std::future::pending().await,
+            }
+        };
+
+        match futures::future::select(pin!(deadline), pin!(cancelled)).await {
+            Either::Left(_) => Error::Timeout,
+            Either::Right(_) => Error::Cancelled,
+        }
+    }
+
+    /// Run a future until it completes or the stop is reached
+    pub async fn run<T>(&self, future: impl Future<Output = T>) -> Result<T, Error> {
+        if let Some(reason) = self.reason() {
+            return Err(reason);
+        }
+
+        match futures::future::select(pin!(future), pin!(self.reached())).await {
+            Either::Left((value, _)) => Ok(value),
+            Either::Right((reason, _)) => Err(reason),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_stop() {
+        // deadline
+        {
+            let stop = Stop::new(Some(Duration::from_millis(20)), None);
+            let result = stop.run(tokio::time::sleep(Duration::from_secs(5))).await;
+            assert!(matches!(result, Err(Error::Timeout)));
+            assert!(matches!(stop.reason(), Some(Error::Timeout)));
+        }
+
+        // cancellation
+        {
+            let token = CancellationToken::new();
+            let stop = Stop::new(None, Some(token.clone()));
+            tokio::spawn({
+                let token = token.clone();
+                async move {
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    token.cancel();
+                }
+            });
+            let result = stop.run(tokio::time::sleep(Duration::from_secs(5))).await;
+            assert!(matches!(result, Err(Error::Cancelled)));
+            assert!(token.is_cancelled());
+        }
+
+        // completed
+        {
+            let stop = Stop::new(Some(Duration::from_secs(5)), None);
+            assert!(matches!(stop.run(async { 1 }).await, Ok(1)));
+        }
+    }
+}
@@ -0,0 +1,238 @@
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+
+use futures::Stream;
+
+use crate::command::{
+    master_result_with_options, master_results_with_options, server_addresses_async_with_options,
+};
+use crate::error::{Error, Result};
+use crate::master_address::MasterAddress;
+use crate::master_event::{master_events_with_options, MasterEvent};
+use crate::master_result::{MasterResult, MasterResults};
+use crate::protocol::MasterProtocol;
+use crate::query_options::{QueryOptions, SourceAddressPolicy};
+use crate::server_address::ServerAddress;
+use crate::server_status::{server_status_from_many_with_options, ServerStatusResult};
+use crate::shared_socket::SharedSocket;
+
+/// Client that sends all queries from a single UDP socket, reused across calls
+///
+/// Replies are matched to queries by source address, so masters can be queried in parallel
+/// without a socket per master. Queries to the same address are run one at a time, the redundant
+/// requests of a [`RetryPolicy`](crate::RetryPolicy) are sent together as part of one query.
+/// [`SourceAddressPolicy::Any`] can not be matched to a query and is rejected by
+/// [`bind`](MasterClient::bind).
+///
+/// The socket is bound to the bind address of the options (default: `0.0.0.0:0`),
+/// so all masters must be reachable from that address family.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use masterstat::{MasterClient, QueryOptions, QuakeWorld};
+///
+/// async fn test() -> masterstat::Result<()> {
+///     let options = QueryOptions::new().timeout(Duration::from_secs(2)).retries(1);
+///     let client = MasterClient::bind(options).await?;
+///
+///     let masters = ["master.quakeworld.nu:27000", "master.quakeservers.net:27000"];
+///     let results = client.master_results(&masters, &QuakeWorld).await;
+///     let statuses = client.server_status_from_many(&results.server_addresses()).await;
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct MasterClient {
+    options: QueryOptions,
+    local_addr: SocketAddr,
+}
+
+impl MasterClient {
+    /// Bind the socket and start receiving replies (requires a tokio runtime)
+    pub async fn bind(options: QueryOptions) -> Result<Self> {
+        if options.source_address_policy == SourceAddressPolicy::Any {
+            return Err(Error::Io(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "SourceAddressPolicy::Any is not supported by MasterClient",
+            )));
+        }
+
+        let bind_address = options
+            .bind_address
+            .unwrap_or((Ipv4Addr::UNSPECIFIED, 0).into());
+        let shared_socket = SharedSocket::bind(bind_address, options.buffer_size).await?;
+        let local_addr = shared_socket.local_addr()?;
+
+        Ok(MasterClient {
+            options: QueryOptions {
+                shared_socket: Some(Arc::new(shared_socket)),
+                ..options
+            },
+            local_addr,
+        })
+    }
+
+    /// Address queries are sent from
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Options used for all queries
+    pub fn options(&self) -> &QueryOptions {
+        &self.options
+    }
+
+    /// Get server addresses from a single master server using the given protocol
+    pub async fn server_addresses(
+        &self,
+        master_address: impl Into<MasterAddress>,
+        protocol: &(impl MasterProtocol + ?Sized),
+    ) -> Result<Vec<ServerAddress>> {
+        server_addresses_async_with_options(master_address, protocol, &self.options).await
+    }
+
+    /// Get server addresses from a single master server using the given protocol,
+    /// including resolved address and timing
+    pub async fn master_result(
+        &self,
+        master_address: impl Into<MasterAddress>,
+        protocol: &(impl MasterProtocol + ?Sized),
+    ) -> MasterResult {
+        master_result_with_options(master_address, protocol, &self.options).await
+    }
+
+    /// Get results from many master servers using the given protocol, one per master (in parallel)
+    pub async fn master_results(
+        &self,
+        master_addresses: &[impl Into<MasterAddress> + Clone],
+        protocol: &(impl MasterProtocol + ?Sized),
+    ) -> MasterResults {
+        master_results_with_options(master_addresses, protocol, &self.options).await
+    }
+
+    /// Get server addresses from many master servers using the given protocol (in parallel)
+    pub async fn server_addresses_from_many(
+        &self,
+        master_addresses: &[impl Into<MasterAddress> + Clone],
+        protocol: &(impl MasterProtocol + ?Sized),
+    ) -> Vec<ServerAddress> {
+        self.master_results(master_addresses, protocol)
+            .await
+            .server_addresses()
+    }
+
+    /// Query many master servers using the given protocol, yielding events as responses arrive
+    /// (in parallel), see [`master_events`](crate::master_events)
+    pub fn master_events<'a>(
+        &self,
+        master_addresses: &[impl Into<MasterAddress> + Clone],
+        protocol: &'a (impl MasterProtocol + ?Sized),
+    ) -> impl Stream<Item = MasterEvent> + 'a {
+        master_events_with_options(master_addresses, protocol, &self.options)
+    }
+
+    /// Get status of many QuakeWorld servers, one result per server (in parallel)
+    pub async fn server_status_from_many(
+        &self,
+        addresses: &[ServerAddress],
+    ) -> Vec<ServerStatusResult> {
+        server_status_from_many_with_options(addresses, &self.options).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use pretty_assertions::assert_eq;
+
+    use crate::protocol::QuakeWorld;
+    use crate::retry::RetryPolicy;
+    use crate::test_util::spawn_handler;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_master_client() -> Result<()> {
+        let options = QueryOptions::new()
+            .timeout(Duration::from_secs(1))
+            .idle_timeout(Duration::from_millis(50))
+            .bind_address("127.0.0.1:0".parse().unwrap());
+        let client = MasterClient::bind(options).await?;
+        let masters = [1, 2, 3]
+            .map(|n| {
+                spawn_handler(move |_| {
+                    vec![vec![
+                        0xff, 0xff, 0xff, 0xff, 0x64, 0x0a, 192, 168, 1, n, 0x75, 0x30,
+                    ]]
+                })
+            })
+            .map(|master| master.to_string());
+
+        // many masters, one socket
+        {
+            let results = client.master_results(&masters, &QuakeWorld).await;
+            assert_eq!(
+                results
+                    .server_addresses()
+                    .iter()
+                    .map(|a| a.to_string())
+                    .collect::<Vec<String>>(),
+                vec![
+                    "192.168.1.1:30000",
+                    "192.168.1.2:30000",
+                    "192.168.1.3:30000"
+                ]
+            );
+        }
+
+        // same master, reused across calls
+        {
+            let (first, second) = futures::join!(
+                client.server_addresses(&masters[0], &QuakeWorld),
+                client.server_addresses(&masters[0], &QuakeWorld),
+            );
+            assert_eq!(first?.len(), 1);
+            assert_eq!(second?.len(), 1);
+        }
+
+        // redundant requests are sent together, not one query after another
+        {
+            let mut requests = 0u8;
+            let master = spawn_handler(move |_| {
+                requests += 1;
+                vec![vec![
+                    0xff, 0xff, 0xff, 0xff, 0x64, 0x0a, 10, 0, 0, requests, 0x75, 0x30,
+                ]]
+            });
+            let options = QueryOptions::new()
+                .timeout(Duration::from_secs(1))
+                .idle_timeout(Duration::from_millis(300))
+                .bind_address("127.0.0.1:0".parse().unwrap())
+                .retry(RetryPolicy {
+                    redundancy: 3,
+                    ..Default::default()
+                });
+            let client = MasterClient::bind(options).await?;
+            let started = Instant::now();
+            let result = client.master_result(master, &QuakeWorld).await;
+            assert!(started.elapsed() < Duration::from_millis(600));
+            assert_eq!(result.packet_count, 3);
+            assert_eq!(result.outcome?.len(), 3);
+        }
+
+        // replies from any address can not be routed
+        {
+            let options = QueryOptions::new().source_address_policy(SourceAddressPolicy::Any);
+            assert!(matches!(
+                MasterClient::bind(options).await,
+                Err(Error::Io(e)) if e.kind() == io::ErrorKind::InvalidInput
+            ));
+        }
+
+        Ok(())
+    }
+}
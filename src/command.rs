@@ -1,17 +1,25 @@
-use std::sync::Arc;
-use std::time::Duration;
+use std::cell::RefCell;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
 
-use anyhow::{anyhow as e, Result};
-use tokio::sync::Mutex;
-use zerocopy::FromBytes;
+use futures::StreamExt;
 
-use crate::server_address::{RawServerAddress, ServerAddress, RAW_ADDRESS_SIZE};
-
-const SERVERS_COMMAND: [u8; 3] = [0x63, 0x0a, 0x00];
-const SERVERS_RESPONSE_HEADER: [u8; 6] = [0xff, 0xff, 0xff, 0xff, 0x64, 0x0a];
+use crate::cancel::Stop;
+use crate::error::{Error, Result};
+use crate::master_address::MasterAddress;
+use crate::master_event::{indexed_master_events, MasterEvent};
+use crate::master_result::{MasterResult, MasterResults};
+use crate::protocol::{MasterProtocol, QuakeWorld};
+use crate::query_options::QueryOptions;
+use crate::server_address::ServerAddress;
+use crate::udp;
+use crate::udp::Next;
 
 /// Get server addresses from a single master server
 ///
+/// The master can be anything convertible to a [`MasterAddress`], e.g. `"host:port"`,
+/// a [`SocketAddr`] or a [`ServerAddress`].
+///
 /// # Example
 ///
 /// ```
@@ -25,16 +33,449 @@ const SERVERS_RESPONSE_HEADER: [u8; 6] = [0xff, 0xff, 0xff, 0xff, 0x64, 0x0a];
 /// }
 /// ```
 pub fn server_addresses(
-    master_address: &str,
+    master_address: impl Into<MasterAddress>,
+    timeout: Option<Duration>,
+) -> Result<Vec<ServerAddress>> {
+    server_addresses_with(master_address, &QuakeWorld, timeout)
+}
+
+/// Get server addresses from a single master server using the given protocol
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use masterstat::Quake3;
+///
+/// let master = "master.ioquake3.org:27950";
+/// let timeout = Some(Duration::from_secs(2));
+/// match masterstat::server_addresses_with(&master, &Quake3::new(68), timeout) {
+///     Ok(addresses) => { println!("found {} server addresses", addresses.len()) },
+///     Err(e) => { eprintln!("error: {}", e); }
+/// }
+/// ```
+pub fn server_addresses_with(
+    master_address: impl Into<MasterAddress>,
+    protocol: &(impl MasterProtocol + ?Sized),
+    timeout: Option<Duration>,
+) -> Result<Vec<ServerAddress>> {
+    server_addresses_with_options(
+        master_address,
+        protocol,
+        &QueryOptions::new().timeout(timeout),
+    )
+}
+
+/// Get server addresses from a single master server using the given protocol and options
+///
+/// Sends a single request per attempt, [`RetryPolicy::redundancy`](crate::RetryPolicy::redundancy)
+/// only applies to async queries. Attempts are limited to the remaining time until the deadline,
+/// cancellation is checked before every attempt.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use masterstat::{QueryOptions, QuakeWorld};
+///
+/// let options = QueryOptions::new().timeout(Duration::from_secs(2)).retries(2);
+/// let server_addresses = masterstat::server_addresses_with_options("master.quakeworld.nu:27000", &QuakeWorld, &options);
+/// ```
+pub fn server_addresses_with_options(
+    master_address: impl Into<MasterAddress>,
+    protocol: &(impl MasterProtocol + ?Sized),
+    options: &QueryOptions,
+) -> Result<Vec<ServerAddress>> {
+    let stop = options.stop();
+    let socket_address = udp::resolve(master_address.into().as_str())?;
+    let mut attempts = 0;
+
+    loop {
+        if attempts > 0 {
+            let delay = options.retry.delay(attempts);
+            std::thread::sleep(stop.remaining().map_or(delay, |r| r.min(delay)));
+        }
+        if let Some(reason) = stop.reason() {
+            return Err(reason);
+        }
+        attempts += 1;
+
+        let options = &options.clone().timeout(stop.limit(options.timeout));
+        let attempt = Attempt::merge(vec![collect(socket_address, protocol, options)]);
+
+        if attempt.outcome.is_ok() || attempts > options.retry.retries {
+            return attempt.into_outcome();
+        }
+    }
+}
+
+/// Get server addresses from a single master server (async)
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+///
+/// async fn test() {
+///     let master = "master.quakeworld.nu:27000";
+///     let timeout = Some(Duration::from_secs(2));
+///     match masterstat::server_addresses_async(&master, timeout).await {
+///         Ok(addresses) => { println!("found {} server addresses", addresses.len()) },
+///         Err(e) => { eprintln!("error: {}", e); }
+///     }
+/// }
+/// ```
+pub async fn server_addresses_async(
+    master_address: impl Into<MasterAddress>,
     timeout: Option<Duration>,
 ) -> Result<Vec<ServerAddress>> {
-    let options = tinyudp::ReadOptions {
-        timeout,
-        buffer_size: 16 * 1024, // 16 kb
+    server_addresses_async_with(master_address, &QuakeWorld, timeout).await
+}
+
+/// Get server addresses from a single master server using the given protocol (async)
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use masterstat::DarkPlaces;
+///
+/// async fn test() {
+///     let master = "dpmaster.deathmask.net:27950";
+///     let timeout = Some(Duration::from_secs(2));
+///     let protocol = DarkPlaces::new("Xonotic", 3);
+///     let server_addresses = masterstat::server_addresses_async_with(&master, &protocol, timeout).await;
+/// }
+/// ```
+pub async fn server_addresses_async_with(
+    master_address: impl Into<MasterAddress>,
+    protocol: &(impl MasterProtocol + ?Sized),
+    timeout: Option<Duration>,
+) -> Result<Vec<ServerAddress>> {
+    server_addresses_async_with_options(
+        master_address,
+        protocol,
+        &QueryOptions::new().timeout(timeout),
+    )
+    .await
+}
+
+/// Get server addresses from a single master server using the given protocol and options (async)
+pub async fn server_addresses_async_with_options(
+    master_address: impl Into<MasterAddress>,
+    protocol: &(impl MasterProtocol + ?Sized),
+    options: &QueryOptions,
+) -> Result<Vec<ServerAddress>> {
+    let master_address = master_address.into();
+    let stop = options.stop();
+    query_master(master_address.as_str(), protocol, options, &stop, |_| {})
+        .await
+        .into_outcome()
+}
+
+/// Get server addresses from a single master server, including resolved address and timing (async)
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+///
+/// async fn test() {
+///     let master = "master.quakeworld.nu:27000";
+///     let timeout = Some(Duration::from_secs(2));
+///     let result = masterstat::master_result(&master, timeout).await;
+///     println!("{:?} responded in {:?}", result.socket_address, result.rtt);
+/// }
+/// ```
+pub async fn master_result(
+    master_address: impl Into<MasterAddress>,
+    timeout: Option<Duration>,
+) -> MasterResult {
+    master_result_with(master_address, &QuakeWorld, timeout).await
+}
+
+/// Get server addresses from a single master server using the given protocol,
+/// including resolved address and timing (async)
+pub async fn master_result_with(
+    master_address: impl Into<MasterAddress>,
+    protocol: &(impl MasterProtocol + ?Sized),
+    timeout: Option<Duration>,
+) -> MasterResult {
+    let options = QueryOptions::new().timeout(timeout);
+    master_result_with_options(master_address, protocol, &options).await
+}
+
+/// Get server addresses from a single master server using the given protocol and options,
+/// including resolved address and timing (async)
+pub async fn master_result_with_options(
+    master_address: impl Into<MasterAddress>,
+    protocol: &(impl MasterProtocol + ?Sized),
+    options: &QueryOptions,
+) -> MasterResult {
+    let master_address = master_address.into();
+    query_master(
+        master_address.as_str(),
+        protocol,
+        options,
+        &options.stop(),
+        |_| {},
+    )
+    .await
+}
+
+/// Query a single master server, `on_entries` is called with the entries of each response packet
+///
+/// Once `stop` is reached the query is abandoned. The addresses received so far are kept as
+/// an incomplete result, without any it fails with the reason for stopping.
+pub(crate) async fn query_master(
+    master_address: &str,
+    protocol: &(impl MasterProtocol + ?Sized),
+    options: &QueryOptions,
+    stop: &Stop,
+    mut on_entries: impl FnMut(&[ServerAddress]),
+) -> MasterResult {
+    let mut result = empty_result(master_address);
+    let mut received = vec![];
+
+    let query = async {
+        match udp::resolve_async_within(master_address, options.resolve_timeout).await {
+            Ok(socket_address) => {
+                query_resolved(&mut result, socket_address, protocol, options, |entries| {
+                    received.extend_from_slice(entries);
+                    on_entries(entries);
+                })
+                .await
+            }
+            Err(e) => result.outcome = Err(e),
+        }
     };
-    let response = tinyudp::send_and_read(master_address, &SERVERS_COMMAND, &options)?;
-    let server_addresses = parse_servers_response(&response)?;
-    Ok(sorted_and_unique(&server_addresses))
+    if let Err(reason) = stop.run(query).await {
+        result.complete = false;
+        result.outcome = match received.is_empty() {
+            true => Err(reason),
+            false => Ok(sorted_and_unique(&received)),
+        };
+    }
+
+    result
+}
+
+fn empty_result(master_address: &str) -> MasterResult {
+    MasterResult {
+        master_address: master_address.to_string(),
+        socket_address: None,
+        outcome: Ok(vec![]),
+        rtt: None,
+        packet_count: 0,
+        attempts: 0,
+        complete: true,
+    }
+}
+
+/// Query a resolved master server, retrying failed attempts
+///
+/// Packet count and round trip time are recorded as packets arrive,
+/// so they are kept if the query is abandoned midway.
+async fn query_resolved(
+    result: &mut MasterResult,
+    socket_address: SocketAddr,
+    protocol: &(impl MasterProtocol + ?Sized),
+    options: &QueryOptions,
+    on_entries: impl FnMut(&[ServerAddress]),
+) {
+    result.socket_address = Some(socket_address);
+    let on_entries = RefCell::new(on_entries);
+
+    loop {
+        if result.attempts > 0 {
+            tokio::time::sleep(options.retry.delay(result.attempts)).await;
+        }
+        result.attempts += 1;
+
+        // a shared socket routes replies by address, all copies go through one exchange
+        let redundancy = options.retry.redundancy.max(1);
+        let exchanges = match options.shared_socket {
+            Some(_) => vec![redundancy],
+            None => vec![1; redundancy as usize],
+        };
+
+        let received = RefCell::new(&mut *result);
+        let copies = exchanges.into_iter().map(|copies| {
+            collect_async(socket_address, protocol, copies, options, |rtt, entries| {
+                let mut result = received.borrow_mut();
+                result.packet_count += 1;
+                result.rtt = Some(result.rtt.map_or(rtt, |r| r.min(rtt)));
+                if !entries.is_empty() {
+                    (on_entries.borrow_mut())(entries);
+                }
+            })
+        });
+        let attempt = Attempt::merge(futures::future::join_all(copies).await);
+
+        result.outcome = attempt.outcome;
+        result.complete = attempt.complete;
+
+        if result.is_ok() || result.attempts > options.retry.retries {
+            return;
+        }
+    }
+}
+
+/// Outcome of one attempt, merged from the responses to its redundant requests
+struct Attempt {
+    outcome: Result<Vec<ServerAddress>>,
+    complete: bool,
+}
+
+impl Attempt {
+    /// Union of the successful responses, the first error if none succeeded
+    ///
+    /// The union is complete if any of the responses is complete.
+    fn merge(copies: Vec<Result<Collected>>) -> Self {
+        let mut server_addresses = vec![];
+        let mut error = None;
+        let mut is_ok = false;
+        let mut complete = false;
+
+        for copy in copies {
+            let outcome = copy.and_then(|collected| {
+                complete |= collected.complete;
+                collected.outcome
+            });
+            match outcome {
+                Ok(entries) => {
+                    server_addresses.extend(entries);
+                    is_ok = true;
+                }
+                Err(e) => {
+                    error.get_or_insert(e);
+                }
+            }
+        }
+
+        let outcome = match (is_ok, error) {
+            (false, Some(e)) => Err(e),
+            _ => Ok(sorted_and_unique(&server_addresses)),
+        };
+
+        Attempt { outcome, complete }
+    }
+
+    /// Server addresses, [`Error::Incomplete`] if the list was cut short
+    fn into_outcome(self) -> Result<Vec<ServerAddress>> {
+        incomplete_as_error(self.outcome, self.complete)
+    }
+}
+
+/// Turn an incomplete list into [`Error::Incomplete`], for functions without a completeness flag
+pub(crate) fn incomplete_as_error(
+    outcome: Result<Vec<ServerAddress>>,
+    complete: bool,
+) -> Result<Vec<ServerAddress>> {
+    match (outcome, complete) {
+        (Ok(server_addresses), false) => Err(Error::Incomplete { server_addresses }),
+        (outcome, _) => outcome,
+    }
+}
+
+/// Server addresses decoded from the response to one request
+struct Collected {
+    outcome: Result<Vec<ServerAddress>>,
+    /// False if a paging master stopped answering before the last page
+    complete: bool,
+}
+
+/// Query a resolved master server, returns the decoded server addresses
+fn collect(
+    socket_address: SocketAddr,
+    protocol: &(impl MasterProtocol + ?Sized),
+    options: &QueryOptions,
+) -> Result<Collected> {
+    let mut collector = Collector::new(protocol);
+    udp::exchange(socket_address, &protocol.request(), options, |p| {
+        collector.on_packet(p)
+    })?;
+    Ok(collector.finish())
+}
+
+/// Send `copies` of the request to a resolved master server, returns the decoded server addresses
+/// of all responses (async)
+///
+/// `on_packet` is called for every response packet with the time since the request
+/// and the server addresses it added.
+async fn collect_async(
+    socket_address: SocketAddr,
+    protocol: &(impl MasterProtocol + ?Sized),
+    copies: u32,
+    options: &QueryOptions,
+    mut on_packet: impl FnMut(Duration, &[ServerAddress]),
+) -> Result<Collected> {
+    let mut collector = Collector::new(protocol);
+    let started = Instant::now();
+    udp::exchange_copies_async(socket_address, &protocol.request(), copies, options, |p| {
+        let received = collector.server_addresses.len();
+        let next = collector.on_packet(p);
+        on_packet(started.elapsed(), &collector.server_addresses[received..]);
+        next
+    })
+    .await?;
+    Ok(collector.finish())
+}
+
+/// Get results from many master servers, one per master (async, in parallel)
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+///
+/// async fn test() {
+///     let masters = ["master.quakeworld.nu:27000", "master.quakeservers.net:27000"];
+///     let timeout = Some(Duration::from_secs(2));
+///     let results = masterstat::master_results(&masters, timeout).await;
+///
+///     for result in results.errors() {
+///         eprintln!("{}: {:?}", result.master_address, result.outcome);
+///     }
+///
+///     let server_addresses = results.server_addresses();
+/// }
+/// ```
+pub async fn master_results(
+    master_addresses: &[impl Into<MasterAddress> + Clone],
+    timeout: Option<Duration>,
+) -> MasterResults {
+    master_results_with(master_addresses, &QuakeWorld, timeout).await
+}
+
+/// Get results from many master servers using the given protocol, one per master (async, in parallel)
+pub async fn master_results_with(
+    master_addresses: &[impl Into<MasterAddress> + Clone],
+    protocol: &(impl MasterProtocol + ?Sized),
+    timeout: Option<Duration>,
+) -> MasterResults {
+    let options = QueryOptions::new().timeout(timeout);
+    master_results_with_options(master_addresses, protocol, &options).await
+}
+
+/// Get results from many master servers using the given protocol and options, one per master (async, in parallel)
+pub async fn master_results_with_options(
+    master_addresses: &[impl Into<MasterAddress> + Clone],
+    protocol: &(impl MasterProtocol + ?Sized),
+    options: &QueryOptions,
+) -> MasterResults {
+    let mut results = master_addresses.iter().map(|_| None).collect::<Vec<_>>();
+    let mut events = indexed_master_events(master_addresses, protocol, options);
+
+    while let Some((index, event)) = events.next().await {
+        if let MasterEvent::Finished(result) | MasterEvent::Failed(result) = event {
+            results[index] = Some(result);
+        }
+    }
+
+    MasterResults {
+        results: results.into_iter().flatten().collect(),
+    }
 }
 
 /// Get server addresses from many master servers (async, in parallel)
@@ -51,44 +492,113 @@ pub fn server_addresses(
 /// }
 /// ```
 pub async fn server_addresses_from_many(
-    master_addresses: &[impl AsRef<str>],
+    master_addresses: &[impl Into<MasterAddress> + Clone],
     timeout: Option<Duration>,
 ) -> Vec<ServerAddress> {
-    let mut task_handles = vec![];
-    let result_mux = Arc::<Mutex<Vec<ServerAddress>>>::default();
+    server_addresses_from_many_with(master_addresses, &QuakeWorld, timeout).await
+}
 
-    for master_address in master_addresses.iter().map(|a| a.as_ref().to_string()) {
-        let result_mux = result_mux.clone();
+/// Get server addresses from many master servers using the given protocol (async, in parallel)
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use masterstat::Quake3;
+///
+/// async fn test() {
+///     let masters = ["master.ioquake3.org:27950", "dpmaster.deathmask.net:27950"];
+///     let timeout = Some(Duration::from_secs(2));
+///     let server_addresses = masterstat::server_addresses_from_many_with(&masters, &Quake3::new(68), timeout).await;
+/// }
+/// ```
+pub async fn server_addresses_from_many_with(
+    master_addresses: &[impl Into<MasterAddress> + Clone],
+    protocol: &(impl MasterProtocol + ?Sized),
+    timeout: Option<Duration>,
+) -> Vec<ServerAddress> {
+    let options = QueryOptions::new().timeout(timeout);
+    server_addresses_from_many_with_options(master_addresses, protocol, &options).await
+}
 
-        let task = tokio::spawn(async move {
-            if let Ok(servers) = server_addresses(&master_address, timeout) {
-                let mut result = result_mux.lock().await;
-                result.extend(servers);
-            }
-        });
-        task_handles.push(task);
+/// Get server addresses from many master servers using the given protocol and options (async, in parallel)
+pub async fn server_addresses_from_many_with_options(
+    master_addresses: &[impl Into<MasterAddress> + Clone],
+    protocol: &(impl MasterProtocol + ?Sized),
+    options: &QueryOptions,
+) -> Vec<ServerAddress> {
+    master_results_with_options(master_addresses, protocol, options)
+        .await
+        .server_addresses()
+}
+
+/// Decodes response packets as they arrive and decides how to continue the exchange
+struct Collector<'a, P: MasterProtocol + ?Sized> {
+    protocol: &'a P,
+    server_addresses: Vec<ServerAddress>,
+    error: Option<Error>,
+    /// Follow-up requests sent to a paging master
+    requests: Vec<Vec<u8>>,
+    /// The master sent the last packet
+    is_finished: bool,
+}
+
+impl<'a, P: MasterProtocol + ?Sized> Collector<'a, P> {
+    fn new(protocol: &'a P) -> Self {
+        Collector {
+            protocol,
+            server_addresses: vec![],
+            error: None,
+            requests: vec![],
+            is_finished: false,
+        }
     }
 
-    futures::future::join_all(task_handles).await;
+    fn on_packet(&mut self, packet: &[u8]) -> Next {
+        let decoded = self
+            .protocol
+            .strip_header(packet)
+            .and_then(|body| Ok((body, self.protocol.decode_entries(body)?)));
 
-    let server_addresses = result_mux.lock().await.clone();
-    sorted_and_unique(&server_addresses)
-}
+        let (body, entries) = match decoded {
+            Ok(decoded) => decoded,
+            Err(e) => {
+                self.error = Some(e);
+                return Next::Done;
+            }
+        };
 
-fn parse_servers_response(response: &[u8]) -> Result<Vec<ServerAddress>> {
-    if !response.starts_with(&SERVERS_RESPONSE_HEADER) {
-        return Err(e!("Invalid response"));
+        if self.protocol.is_last_packet(body) {
+            self.server_addresses.extend(entries);
+            self.is_finished = true;
+            return Next::Done;
+        }
+
+        let next = match self.protocol.next_request(&entries) {
+            // a page that was already requested is a duplicate, keep waiting for the next page
+            Some(request) if self.requests.contains(&request) => return Next::Read,
+            Some(request) => {
+                self.requests.push(request.clone());
+                Next::Send(request)
+            }
+            None => Next::Read,
+        };
+        self.server_addresses.extend(entries);
+        next
     }
 
-    let body = &response[SERVERS_RESPONSE_HEADER.len()..];
-    let server_addresses = body
-        .chunks(RAW_ADDRESS_SIZE)
-        .filter(|b| b.len() == RAW_ADDRESS_SIZE)
-        .filter_map(RawServerAddress::read_from)
-        .map(ServerAddress::from)
-        .collect::<Vec<ServerAddress>>();
+    /// Decoded server addresses, incomplete if a paging master never sent the last page
+    fn finish(self) -> Collected {
+        let is_paging = !self.requests.is_empty();
 
-    Ok(server_addresses)
+        Collected {
+            outcome: match self.error {
+                Some(e) => Err(e),
+                None => Ok(sorted_and_unique(&self.server_addresses)),
+            },
+            complete: !is_paging || self.is_finished,
+        }
+    }
 }
 
 pub fn sorted_and_unique(server_addresses: &[ServerAddress]) -> Vec<ServerAddress> {
@@ -102,53 +612,471 @@ pub fn sorted_and_unique(server_addresses: &[ServerAddress]) -> Vec<ServerAddres
 mod tests {
     use pretty_assertions::assert_eq;
 
+    use crate::cancel::CancellationToken;
+    use crate::protocol::Valve;
+    use crate::retry::RetryPolicy;
+    use crate::test_util::{spawn_handler, spawn_responder};
+
     use super::*;
 
+    fn parse_response(
+        protocol: &impl MasterProtocol,
+        packets: &[Vec<u8>],
+    ) -> Result<Vec<ServerAddress>> {
+        let mut collector = Collector::new(protocol);
+        for packet in packets {
+            if let Next::Done = collector.on_packet(packet) {
+                break;
+            }
+        }
+        collector.finish().outcome
+    }
+
+    #[tokio::test]
+    async fn test_master_results() {
+        let responding = spawn_responder(vec![
+            vec![
+                0xff, 0xff, 0xff, 0xff, 0x64, 0x0a, 192, 168, 1, 2, 0x75, 0x30,
+            ],
+            vec![
+                0xff, 0xff, 0xff, 0xff, 0x64, 0x0a, 192, 168, 1, 1, 0x75, 0x30,
+            ],
+        ]);
+        let silent = spawn_responder(vec![]);
+        let masters = [responding.to_string(), silent.to_string()];
+        let results = master_results(&masters, Some(Duration::from_millis(200))).await;
+
+        assert_eq!(results.results.len(), 2);
+
+        let first = &results.results[0];
+        assert_eq!(first.master_address, responding.to_string());
+        assert_eq!(first.socket_address, Some(responding));
+        assert_eq!(first.packet_count, 2);
+        assert!(first.rtt.is_some());
+        assert_eq!(first.outcome.as_ref().unwrap().len(), 2);
+
+        let second = &results.results[1];
+        assert_eq!(second.socket_address, Some(silent));
+        assert_eq!(second.packet_count, 0);
+        assert_eq!(second.rtt, None);
+        assert!(matches!(second.outcome, Err(Error::Timeout)));
+
+        let server_addresses = results.server_addresses();
+        assert_eq!(server_addresses.len(), 2);
+        assert_eq!(server_addresses[0].to_string(), "192.168.1.1:30000");
+    }
+
+    #[tokio::test]
+    async fn test_server_addresses_master_address() -> Result<()> {
+        let response = vec![
+            0xff, 0xff, 0xff, 0xff, 0x64, 0x0a, 192, 168, 1, 1, 0x75, 0x30,
+        ];
+        let expected = vec!["192.168.1.1:30000".parse().unwrap()];
+        let timeout = Some(Duration::from_millis(200));
+
+        // socket address
+        let master = spawn_responder(vec![response.clone()]);
+        assert_eq!(server_addresses_async(master, timeout).await?, expected);
+
+        // (ip, port) tuple
+        let master = spawn_responder(vec![response.clone()]);
+        assert_eq!(
+            server_addresses_async((master.ip(), master.port()), timeout).await?,
+            expected
+        );
+
+        // server address
+        let master = ServerAddress::from(spawn_responder(vec![response.clone()]));
+        assert_eq!(server_addresses_async(&master, timeout).await?, expected);
+
+        // server address (sync)
+        let master = ServerAddress::from(spawn_responder(vec![response]));
+        let result = tokio::task::spawn_blocking(move || server_addresses(master, timeout))
+            .await
+            .unwrap();
+        assert_eq!(result?, expected);
+
+        Ok(())
+    }
+
     #[test]
-    fn test_parse_servers_response() -> Result<()> {
+    fn test_parse_response() -> Result<()> {
         // invalid response header
         {
-            let response = [0xff, 0xff];
-            let result = parse_servers_response(&response);
-            assert_eq!(result.unwrap_err().to_string(), "Invalid response");
+            let response = [vec![0xff, 0xff]];
+            let result = parse_response(&QuakeWorld, &response);
+            assert!(
+                matches!(result, Err(Error::InvalidHeader { packet }) if packet == [0xff, 0xff])
+            );
         }
 
         // valid response
         {
-            let response = [
+            let response = [vec![
                 0xff, 0xff, 0xff, 0xff, 0x64, 0x0a, 192, 168, 1, 1, 0x75, 0x30, 192, 168, 1, 2,
                 0x75, 0x30,
-            ];
-            let result = parse_servers_response(&response)?;
+            ]];
+            let result = parse_response(&QuakeWorld, &response)?;
             assert_eq!(result.len(), 2);
-            assert_eq!(result[0].ip, "192.168.1.1");
+            assert_eq!(result[0].to_string(), "192.168.1.1:30000");
             assert_eq!(result[0].port, 30000);
-            assert_eq!(result[1].ip, "192.168.1.2");
+            assert_eq!(result[1].to_string(), "192.168.1.2:30000");
             assert_eq!(result[1].port, 30000);
         }
 
+        // valid response split across packets
+        {
+            let response = [
+                vec![
+                    0xff, 0xff, 0xff, 0xff, 0x64, 0x0a, 192, 168, 1, 1, 0x75, 0x30,
+                ],
+                vec![
+                    0xff, 0xff, 0xff, 0xff, 0x64, 0x0a, 192, 168, 1, 2, 0x75, 0x30,
+                ],
+            ];
+            let result = parse_response(&QuakeWorld, &response)?;
+            assert_eq!(result.len(), 2);
+            assert_eq!(result[0].to_string(), "192.168.1.1:30000");
+            assert_eq!(result[1].to_string(), "192.168.1.2:30000");
+        }
+
+        // invalid header in any packet
+        {
+            let response = [
+                vec![
+                    0xff, 0xff, 0xff, 0xff, 0x64, 0x0a, 192, 168, 1, 1, 0x75, 0x30,
+                ],
+                vec![0xff, 0xff, 192, 168, 1, 2, 0x75, 0x30],
+            ];
+            let result = parse_response(&QuakeWorld, &response);
+            assert!(matches!(result, Err(Error::InvalidHeader { .. })));
+        }
+
+        // truncated body
+        {
+            let response = [vec![
+                0xff, 0xff, 0xff, 0xff, 0x64, 0x0a, 192, 168, 1, 1, 0x75, 0x30, 192, 168,
+            ]];
+            let result = parse_response(&QuakeWorld, &response);
+            assert!(
+                matches!(result, Err(Error::TruncatedBody { remainder }) if remainder == [192, 168])
+            );
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_master_result_retry() {
+        let timeout = Some(Duration::from_millis(100));
+        let retry = RetryPolicy {
+            retries: 2,
+            backoff: Duration::from_millis(10),
+            ..Default::default()
+        };
+
+        // first request lost, second answered
+        {
+            let mut requests = 0;
+            let master = spawn_handler(move |_| {
+                requests += 1;
+                match requests {
+                    1 => vec![],
+                    _ => vec![vec![
+                        0xff, 0xff, 0xff, 0xff, 0x64, 0x0a, 192, 168, 1, 1, 0x75, 0x30,
+                    ]],
+                }
+            });
+            let options = QueryOptions::new().timeout(timeout).retry(retry.clone());
+            let result = master_result_with_options(master, &QuakeWorld, &options).await;
+            assert_eq!(result.attempts, 2);
+            assert_eq!(result.outcome.unwrap().len(), 1);
+        }
+
+        // all attempts fail
+        {
+            let master = spawn_handler(|_| vec![]);
+            let options = QueryOptions::new().timeout(timeout).retry(retry.clone());
+            let result = master_result_with_options(master, &QuakeWorld, &options).await;
+            assert_eq!(result.attempts, 3);
+            assert!(matches!(result.outcome, Err(Error::Timeout)));
+        }
+
+        // redundant requests, each response is partial
+        {
+            let mut requests = 0u8;
+            let master = spawn_handler(move |_| {
+                requests += 1;
+                vec![vec![
+                    0xff, 0xff, 0xff, 0xff, 0x64, 0x0a, 192, 168, 1, requests, 0x75, 0x30,
+                ]]
+            });
+            let retry = RetryPolicy {
+                redundancy: 3,
+                ..Default::default()
+            };
+            let options = QueryOptions::new().timeout(timeout).retry(retry.clone());
+            let result = master_result_with_options(master, &QuakeWorld, &options).await;
+            assert_eq!(result.attempts, 1);
+            assert_eq!(result.packet_count, 3);
+            assert_eq!(
+                result
+                    .outcome
+                    .unwrap()
+                    .iter()
+                    .map(|s| s.to_string())
+                    .collect::<Vec<String>>(),
+                vec![
+                    "192.168.1.1:30000",
+                    "192.168.1.2:30000",
+                    "192.168.1.3:30000"
+                ]
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_with_options() {
+        let response = vec![
+            0xff, 0xff, 0xff, 0xff, 0x64, 0x0a, 192, 168, 1, 1, 0x75, 0x30,
+        ];
+
+        // retries (blocking)
+        {
+            let mut requests = 0;
+            let master = spawn_handler({
+                let response = response.clone();
+                move |_| {
+                    requests += 1;
+                    match requests {
+                        1 => vec![],
+                        _ => vec![response.clone()],
+                    }
+                }
+            });
+            let options = QueryOptions::new()
+                .timeout(Duration::from_millis(100))
+                .retry(RetryPolicy {
+                    retries: 1,
+                    backoff: Duration::from_millis(10),
+                    ..Default::default()
+                });
+            let result = tokio::task::spawn_blocking(move || {
+                server_addresses_with_options(master, &QuakeWorld, &options)
+            })
+            .await
+            .unwrap();
+            assert_eq!(result.unwrap().len(), 1);
+        }
+
+        // one master at a time, results in the order of the masters
+        {
+            let masters = [
+                spawn_responder(vec![response.clone()]).to_string(),
+                spawn_responder(vec![]).to_string(),
+                spawn_responder(vec![response.clone()]).to_string(),
+            ];
+            let options = QueryOptions::new()
+                .timeout(Duration::from_millis(100))
+                .concurrency(1);
+            let results = master_results_with_options(&masters, &QuakeWorld, &options).await;
+            assert_eq!(
+                results
+                    .results
+                    .iter()
+                    .map(|r| (r.master_address.as_str(), r.is_ok()))
+                    .collect::<Vec<_>>(),
+                vec![
+                    (masters[0].as_str(), true),
+                    (masters[1].as_str(), false),
+                    (masters[2].as_str(), true)
+                ]
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_deadline_and_cancellation() {
+        let response = vec![
+            0xff, 0xff, 0xff, 0xff, 0x64, 0x0a, 192, 168, 1, 1, 0x75, 0x30,
+        ];
+        let started = Instant::now();
+
+        // deadline, results so far
+        {
+            let masters = [
+                spawn_responder(vec![response.clone()]).to_string(),
+                spawn_responder(vec![]).to_string(),
+            ];
+            let options = QueryOptions::new()
+                .timeout(Duration::from_secs(5))
+                .idle_timeout(Duration::from_millis(50))
+                .deadline(Duration::from_millis(300));
+            let results = master_results_with_options(&masters, &QuakeWorld, &options).await;
+            assert_eq!(results.results[0].outcome.as_ref().unwrap().len(), 1);
+            assert!(matches!(results.results[1].outcome, Err(Error::Timeout)));
+        }
+
+        // cancellation
+        {
+            let master = spawn_responder(vec![]).to_string();
+            let token = CancellationToken::new();
+            let options = QueryOptions::new().cancellation_token(token.clone());
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                token.cancel();
+            });
+            let result = master_result_with_options(master, &QuakeWorld, &options).await;
+            assert!(matches!(result.outcome, Err(Error::Cancelled)));
+        }
+
+        // deadline after the first packet, partial list
+        {
+            let master = spawn_responder(vec![response.clone()]);
+            let options = QueryOptions::new()
+                .idle_timeout(Duration::from_secs(5))
+                .deadline(Duration::from_millis(200));
+            let result = master_result_with_options(master, &QuakeWorld, &options).await;
+            assert_eq!(result.outcome.as_ref().unwrap().len(), 1);
+            assert!(!result.complete);
+            assert_eq!(result.attempts, 1);
+            assert_eq!(result.packet_count, 1);
+            assert!(result.rtt.is_some());
+
+            let master = spawn_responder(vec![response.clone()]);
+            let result = server_addresses_async_with_options(master, &QuakeWorld, &options).await;
+            assert!(
+                matches!(result, Err(Error::Incomplete { server_addresses }) if server_addresses.len() == 1)
+            );
+        }
+
+        // deadline (sync)
+        {
+            let master = spawn_responder(vec![]);
+            let options = QueryOptions::new()
+                .retries(5)
+                .deadline(Duration::from_millis(200));
+            let result = tokio::task::spawn_blocking(move || {
+                server_addresses_with_options(master, &QuakeWorld, &options)
+            })
+            .await
+            .unwrap();
+            assert!(matches!(result, Err(Error::Timeout)));
+        }
+
+        // already cancelled
+        {
+            let token = CancellationToken::new();
+            token.cancel();
+            let options = QueryOptions::new().cancellation_token(token);
+            let result =
+                server_addresses_async_with_options("127.0.0.1:1", &QuakeWorld, &options).await;
+            assert!(matches!(result, Err(Error::Cancelled)));
+        }
+
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[tokio::test]
+    async fn test_server_addresses_async_with_paging() -> Result<()> {
+        let header = [0xff, 0xff, 0xff, 0xff, 0x66, 0x0a];
+
+        // pages until terminator
+        {
+            let master = spawn_handler(move |request| {
+                let seed = &request[2..request.iter().position(|b| *b == 0).unwrap()];
+                let body: &[u8] = match seed {
+                    b"0.0.0.0:0" => &[10, 0, 0, 2, 0x69, 0x87, 10, 0, 0, 1, 0x69, 0x87],
+                    b"10.0.0.1:27015" => &[10, 0, 0, 3, 0x69, 0x87, 0, 0, 0, 0, 0, 0],
+                    _ => &[],
+                };
+                vec![[&header, body].concat()]
+            });
+            let result = server_addresses_async_with(
+                &master.to_string(),
+                &Valve::new(""),
+                Some(Duration::from_secs(1)),
+            )
+            .await?;
+            assert_eq!(
+                result.iter().map(|a| a.to_string()).collect::<Vec<_>>(),
+                vec!["10.0.0.1:27015", "10.0.0.2:27015", "10.0.0.3:27015"]
+            );
+        }
+
+        // repeated page without terminator
+        {
+            let master =
+                spawn_handler(move |_| vec![[&header[..], &[10, 0, 0, 1, 0x69, 0x87]].concat()]);
+            let result = server_addresses_async_with(
+                &master.to_string(),
+                &Valve::new(""),
+                Some(Duration::from_millis(300)),
+            )
+            .await;
+            assert!(matches!(
+                result,
+                Err(Error::Incomplete { server_addresses }) if server_addresses.len() == 1
+            ));
+        }
+
+        // duplicated page, list continues
+        {
+            let master = spawn_handler(move |request| {
+                let seed = &request[2..request.iter().position(|b| *b == 0).unwrap()];
+                match seed {
+                    b"0.0.0.0:0" => {
+                        let page = [&header[..], &[10, 0, 0, 1, 0x69, 0x87]].concat();
+                        vec![page.clone(), page]
+                    }
+                    _ => vec![[&header[..], &[10, 0, 0, 2, 0x69, 0x87, 0, 0, 0, 0, 0, 0]].concat()],
+                }
+            });
+            let result = server_addresses_async_with(
+                &master.to_string(),
+                &Valve::new(""),
+                Some(Duration::from_secs(1)),
+            )
+            .await?;
+            assert_eq!(
+                result.iter().map(|a| a.to_string()).collect::<Vec<_>>(),
+                vec!["10.0.0.1:27015", "10.0.0.2:27015"]
+            );
+        }
+
+        // master stops answering before the last page
+        {
+            let master =
+                spawn_handler(
+                    move |request| match request[2..].starts_with(b"0.0.0.0:0") {
+                        true => vec![[&header[..], &[10, 0, 0, 1, 0x69, 0x87]].concat()],
+                        false => vec![],
+                    },
+                );
+            let result = server_addresses_async_with(
+                &master.to_string(),
+                &Valve::new(""),
+                Some(Duration::from_millis(200)),
+            )
+            .await;
+            assert!(matches!(
+                result,
+                Err(Error::Incomplete { server_addresses }) if server_addresses.len() == 1
+            ));
+        }
+
         Ok(())
     }
 
     #[test]
     fn test_sorted_and_unique() {
-        let server1_1 = ServerAddress {
-            ip: "192.168.1.1".to_string(),
-            port: 1,
-        };
-        let server1_2 = ServerAddress {
-            ip: "192.168.1.1".to_string(),
-            port: 2,
-        };
-        let server3 = ServerAddress {
-            ip: "192.168.1.3".to_string(),
-            port: 1,
-        };
-        let server4 = ServerAddress {
-            ip: "192.168.1.4".to_string(),
-            port: 1,
-        };
+        let server1_1 = ServerAddress::new("192.168.1.1".parse().unwrap(), 1);
+        let server1_2 = ServerAddress::new("192.168.1.1".parse().unwrap(), 2);
+        let server3 = ServerAddress::new("192.168.1.3".parse().unwrap(), 1);
+        let server4 = ServerAddress::new("192.168.1.4".parse().unwrap(), 1);
+        let server10 = ServerAddress::new("192.168.1.10".parse().unwrap(), 1);
         let servers = vec![
+            server10.clone(),
             server4.clone(),
             server4.clone(),
             server4.clone(),
@@ -158,7 +1086,7 @@ mod tests {
         ];
         assert_eq!(
             sorted_and_unique(&servers),
-            vec![server1_1, server1_2, server3, server4]
+            vec![server1_1, server1_2, server3, server4, server10]
         );
     }
 }
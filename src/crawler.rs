@@ -0,0 +1,249 @@
+use std::time::{Duration, Instant};
+
+use crate::cancel::Stop;
+use crate::command::master_results_with_options;
+use crate::master_address::MasterAddress;
+use crate::master_result::MasterResults;
+use crate::protocol::QuakeWorld;
+use crate::query_options::QueryOptions;
+use crate::server_address::ServerAddress;
+use crate::server_status::{server_status_result, ServerStatus, ServerStatusResult};
+use crate::stagger::query_each;
+
+/// Time to wait for each status reply if the query options have no timeout
+pub const DEFAULT_PROBE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Server that replied to the status request
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct LiveServer {
+    pub address: ServerAddress,
+    pub status: ServerStatus,
+    /// Time until the status reply arrived
+    pub rtt: Duration,
+}
+
+/// Result of a crawl, see [`crawl`]
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Snapshot {
+    /// One result per master
+    pub masters: MasterResults,
+    /// Servers that replied (sorted by address)
+    pub servers: Vec<LiveServer>,
+    /// Servers listed by a master that did not reply (sorted)
+    pub unresponsive: Vec<ServerAddress>,
+    /// Servers listed by a master that replied with an invalid status response (sorted)
+    pub invalid: Vec<ServerAddress>,
+    /// Time spent querying master servers
+    pub master_duration: Duration,
+    /// Time spent probing servers
+    pub probe_duration: Duration,
+}
+
+impl Snapshot {
+    /// Total time of the crawl
+    pub fn duration(&self) -> Duration {
+        self.master_duration + self.probe_duration
+    }
+
+    /// Average round-trip time of servers that replied
+    pub fn avg_rtt(&self) -> Option<Duration> {
+        let total = self.servers.iter().map(|s| s.rtt).sum::<Duration>();
+        (!self.servers.is_empty()).then(|| total / self.servers.len() as u32)
+    }
+}
+
+/// Query all QuakeWorld master servers and get the status of every listed server (async)
+///
+/// The options apply to master queries and status requests alike, each status reply is waited
+/// for until the timeout of the options (1 second if not set). The deadline and cancellation
+/// token cover the whole crawl, servers that have not replied by then are listed as unresponsive.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use masterstat::QueryOptions;
+///
+/// async fn test() {
+///     let masters = ["master.quakeworld.nu:27000", "master.quakeservers.net:27000"];
+///     let options = QueryOptions::new()
+///         .timeout(Duration::from_secs(1))
+///         .retries(1)
+///         .concurrency(64)
+///         .deadline(Duration::from_secs(10));
+///     let snapshot = masterstat::crawl(&masters, &options).await;
+///
+///     println!(
+///         "{} servers up, {} down, crawled in {:?}",
+///         snapshot.servers.len(),
+///         snapshot.unresponsive.len(),
+///         snapshot.duration()
+///     );
+/// }
+/// ```
+pub async fn crawl(
+    master_addresses: &[impl Into<MasterAddress> + Clone],
+    options: &QueryOptions,
+) -> Snapshot {
+    let stop = options.stop();
+
+    let started = Instant::now();
+    let masters = master_results_with_options(master_addresses, &QuakeWorld, options).await;
+    let master_duration = started.elapsed();
+
+    let started = Instant::now();
+    let server_addresses = masters.server_addresses();
+    let probe_options = with_probe_timeout(options);
+    let results = query_each(&server_addresses, &probe_options, &stop, |address| {
+        probe(address, &probe_options, &stop)
+    })
+    .await;
+    let probe_duration = started.elapsed();
+
+    let mut servers = vec![];
+    let mut unresponsive = vec![];
+    let mut invalid = vec![];
+
+    for result in results {
+        match (result.outcome, result.rtt) {
+            (Ok(status), Some(rtt)) => servers.push(LiveServer {
+                address: result.address,
+                status,
+                rtt,
+            }),
+            (Err(_), Some(_)) => invalid.push(result.address),
+            _ => unresponsive.push(result.address),
+        }
+    }
+
+    Snapshot {
+        masters,
+        servers,
+        unresponsive,
+        invalid,
+        master_duration,
+        probe_duration,
+    }
+}
+
+/// Options with the default probe timeout if no timeout is set
+fn with_probe_timeout(options: &QueryOptions) -> QueryOptions {
+    let timeout = options.timeout.unwrap_or(DEFAULT_PROBE_TIMEOUT);
+    options.clone().timeout(timeout)
+}
+
+/// Get the status of a server, retrying while it does not reply until `stop` is reached
+async fn probe(address: &ServerAddress, options: &QueryOptions, stop: &Stop) -> ServerStatusResult {
+    let probed = stop
+        .run(async {
+            let mut result = server_status_result(address, options).await;
+
+            for retry in 1..=options.retry.retries {
+                if result.rtt.is_some() {
+                    break;
+                }
+                tokio::time::sleep(options.retry.delay(retry)).await;
+                result = server_status_result(address, options).await;
+            }
+
+            result
+        })
+        .await;
+
+    probed.unwrap_or_else(|reason| ServerStatusResult::failed(address, reason))
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::test_util::{spawn_handler, spawn_responder};
+
+    use super::*;
+
+    fn master_response(servers: &[std::net::SocketAddr]) -> Vec<u8> {
+        let mut packet = vec![0xff, 0xff, 0xff, 0xff, 0x64, 0x0a];
+        for server in servers {
+            let std::net::IpAddr::V4(ip) = server.ip() else {
+                unreachable!()
+            };
+            packet.extend_from_slice(&ip.octets());
+            packet.extend_from_slice(&server.port().to_be_bytes());
+        }
+        packet
+    }
+
+    #[tokio::test]
+    async fn test_crawl() {
+        let live = spawn_handler(|_| vec![b"\xff\xff\xff\xffn\\map\\dm4\n".to_vec()]);
+        let invalid = spawn_handler(|_| vec![b"\xff\xff\xff\xffc\n".to_vec()]);
+        let silent = spawn_responder(vec![]);
+        let listed = master_response(&[live, invalid, silent]);
+        let master = spawn_handler(move |_| vec![listed.clone()]);
+        let silent_master = spawn_responder(vec![]);
+        let masters = [master.to_string(), silent_master.to_string()];
+        let options = QueryOptions::new()
+            .timeout(Duration::from_millis(200))
+            .idle_timeout(Duration::from_millis(50));
+
+        // live, invalid and unresponsive servers
+        {
+            let snapshot = crawl(&masters, &options).await;
+
+            assert_eq!(snapshot.masters.results.len(), 2);
+            assert_eq!(snapshot.masters.errors().count(), 1);
+            assert_eq!(snapshot.servers.len(), 1);
+            assert_eq!(
+                snapshot.servers[0].address,
+                ServerAddress::new(live.ip(), live.port())
+            );
+            assert_eq!(snapshot.servers[0].status.info.map, "dm4");
+            assert_eq!(
+                snapshot.invalid,
+                vec![ServerAddress::new(invalid.ip(), invalid.port())]
+            );
+            assert_eq!(
+                snapshot.unresponsive,
+                vec![ServerAddress::new(silent.ip(), silent.port())]
+            );
+            assert!(snapshot.avg_rtt().is_some());
+            assert!(snapshot.duration() >= snapshot.probe_duration);
+        }
+
+        // deadline covers the probes
+        {
+            let options = QueryOptions::new()
+                .timeout(Duration::from_secs(5))
+                .idle_timeout(Duration::from_millis(50))
+                .deadline(Duration::from_millis(300));
+            let started = Instant::now();
+            let snapshot = crawl(&[master.to_string()], &options).await;
+
+            assert!(started.elapsed() < Duration::from_secs(2));
+            assert_eq!(snapshot.servers.len(), 1);
+            assert_eq!(
+                snapshot.unresponsive,
+                vec![ServerAddress::new(silent.ip(), silent.port())]
+            );
+        }
+
+        // default probe timeout without timeout or deadline
+        {
+            let silent = spawn_handler(|_| vec![]);
+            let listed = master_response(&[silent]);
+            let master = spawn_handler(move |_| vec![listed.clone()]);
+            let options = QueryOptions::new().idle_timeout(Duration::from_millis(50));
+            let started = Instant::now();
+            let snapshot = crawl(&[master.to_string()], &options).await;
+
+            assert!(started.elapsed() >= DEFAULT_PROBE_TIMEOUT);
+            assert!(started.elapsed() < DEFAULT_PROBE_TIMEOUT * 2);
+            assert_eq!(
+                snapshot.unresponsive,
+                vec![ServerAddress::new(silent.ip(), silent.port())]
+            );
+        }
+    }
+}
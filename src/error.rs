@@ -0,0 +1,149 @@
+use std::fmt::{Display, Formatter};
+use std::io;
+
+use crate::server_address::ServerAddress;
+
+/// Errors that can occur when querying master servers and game servers
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// The address could not be resolved
+    Resolve(io::Error),
+    /// No response was received before the timeout expired, or the deadline passed
+    Timeout,
+    /// The query was cancelled with a [`CancellationToken`](crate::CancellationToken)
+    Cancelled,
+    /// Only part of the list was received, e.g. a paging master stopped answering before the
+    /// last page, with the server addresses received so far
+    Incomplete {
+        server_addresses: Vec<ServerAddress>,
+    },
+    /// Socket error while sending or receiving, or error reading a file
+    Io(io::Error),
+    /// A response packet did not start with the expected header
+    InvalidHeader { packet: Vec<u8> },
+    /// The response body ended with an incomplete server address entry
+    TruncatedBody { remainder: Vec<u8> },
+    /// An entry of the response body could not be parsed, e.g. a player line of a status response
+    InvalidEntry { entry: Vec<u8> },
+    /// A line of a known masters config file could not be parsed
+    InvalidConfig { line: usize, content: String },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Resolve(e) => write!(f, "failed to resolve address: {}", e),
+            Error::Timeout => write!(f, "timed out waiting for response"),
+            Error::Cancelled => write!(f, "query was cancelled"),
+            Error::Incomplete { server_addresses } => write!(
+                f,
+                "incomplete response, received {} server addresses",
+                server_addresses.len()
+            ),
+            Error::Io(e) => write!(f, "i/o error: {}", e),
+            Error::InvalidHeader { packet } => {
+                write!(f, "invalid response header: {:02x?}", header_bytes(packet))
+            }
+            Error::TruncatedBody { remainder } => {
+                write!(f, "truncated response body: {:02x?}", remainder)
+            }
+            Error::InvalidEntry { entry } => {
+                write!(
+                    f,
+                    "invalid response entry: {}",
+                    String::from_utf8_lossy(entry)
+                )
+            }
+            Error::InvalidConfig { line, content } => {
+                write!(f, "invalid config on line {}: {}", line, content)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Resolve(e) | Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+/// Serializes as the error message
+#[cfg(feature = "serde")]
+impl serde::Serialize for Error {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Leading bytes of a packet, enough to show a header in error messages
+fn header_bytes(packet: &[u8]) -> &[u8] {
+    &packet[..packet.len().min(16)]
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Error::Timeout.to_string(), "timed out waiting for response");
+        assert_eq!(Error::Cancelled.to_string(), "query was cancelled");
+        assert_eq!(
+            Error::Incomplete {
+                server_addresses: vec![]
+            }
+            .to_string(),
+            "incomplete response, received 0 server addresses"
+        );
+        assert_eq!(
+            Error::InvalidHeader {
+                packet: vec![0xff, 0xff, 0x01]
+            }
+            .to_string(),
+            "invalid response header: [ff, ff, 01]"
+        );
+        assert_eq!(
+            Error::TruncatedBody {
+                remainder: vec![192, 168]
+            }
+            .to_string(),
+            "truncated response body: [c0, a8]"
+        );
+        assert_eq!(
+            Error::InvalidEntry {
+                entry: b"1 2".to_vec()
+            }
+            .to_string(),
+            "invalid response entry: 1 2"
+        );
+        assert_eq!(
+            Error::from(io::Error::other("oops")).to_string(),
+            "i/o error: oops"
+        );
+        assert_eq!(
+            Error::InvalidConfig {
+                line: 3,
+                content: "foo".to_string()
+            }
+            .to_string(),
+            "invalid config on line 3: foo"
+        );
+    }
+}
@@ -0,0 +1,314 @@
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::net::UdpSocket;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+use crate::error::Result;
+use crate::master_address::MasterAddress;
+use crate::protocol::{HEARTBEAT, SHUTDOWN};
+use crate::query_options::QueryOptions;
+use crate::udp;
+
+/// Interval used by QuakeWorld servers (`HEARTBEAT_SECONDS`)
+pub const DEFAULT_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Heartbeat settings for announcing a server to master servers
+///
+/// The local address and the time for resolving masters are taken from [`QueryOptions`].
+#[derive(Clone, Debug)]
+pub struct HeartbeatOptions {
+    /// Time between heartbeats
+    pub interval: Duration,
+}
+
+impl Default for HeartbeatOptions {
+    fn default() -> Self {
+        HeartbeatOptions {
+            interval: DEFAULT_HEARTBEAT_INTERVAL,
+        }
+    }
+}
+
+/// Handle of a running heartbeat loop, see [`heartbeat`]
+///
+/// Dropping the handle stops the loop as well.
+pub struct HeartbeatHandle {
+    local_addr: SocketAddr,
+    player_count: Arc<AtomicUsize>,
+    stop: oneshot::Sender<()>,
+    task: JoinHandle<()>,
+}
+
+impl HeartbeatHandle {
+    /// Address heartbeats are sent from
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Set the player count sent with the next heartbeat
+    pub fn set_player_count(&self, player_count: usize) {
+        self.player_count.store(player_count, Ordering::Relaxed);
+    }
+
+    /// Stop sending heartbeats and send the shutdown packet to all masters
+    pub async fn stop(self) {
+        let _ = self.stop.send(());
+        let _ = self.task.await;
+    }
+}
+
+/// Announce a server to many master servers using QuakeWorld heartbeats
+///
+/// Sends a heartbeat (`a\n<sequence>\n<player count>\n`) to all masters right away and then
+/// once per interval. The shutdown packet (`C\n`) is sent when the handle is stopped or dropped,
+/// the deadline of the options passes or its cancellation token is cancelled.
+///
+/// Masters that can not be resolved are skipped, they are resolved again before every heartbeat.
+///
+/// Masters list the address heartbeats are sent from, so the bind address of the options
+/// should be the address of the game server (default: `0.0.0.0:0`).
+///
+/// # Example
+///
+/// ```
+/// use masterstat::{HeartbeatOptions, QueryOptions};
+///
+/// async fn test() -> masterstat::Result<()> {
+///     let masters = ["master.quakeworld.nu:27000", "master.quakeservers.net:27000"];
+///     let options = QueryOptions::new().bind_address("0.0.0.0:27500".parse().unwrap());
+///     let handle = masterstat::heartbeat(&masters, &HeartbeatOptions::default(), &options).await?;
+///     handle.set_player_count(4);
+///
+///     // ...
+///
+///     handle.stop().await;
+///     Ok(())
+/// }
+/// ```
+pub async fn heartbeat(
+    master_addresses: &[impl Into<MasterAddress> + Clone],
+    heartbeat_options: &HeartbeatOptions,
+    options: &QueryOptions,
+) -> Result<HeartbeatHandle> {
+    let bind_address = options
+        .bind_address
+        .unwrap_or((Ipv4Addr::UNSPECIFIED, 0).into());
+    let socket = UdpSocket::bind(bind_address).await?;
+    heartbeat_with_socket(
+        Arc::new(socket),
+        master_addresses,
+        heartbeat_options,
+        options,
+    )
+}
+
+/// Announce a server to many master servers, sending heartbeats from an existing socket
+///
+/// Same as [`heartbeat`], but uses the socket of the game server so that heartbeats are
+/// sent from the game port. Packets are only sent, the game server keeps receiving from
+/// the socket as usual. The bind address of the options is not used.
+///
+/// # Example
+///
+/// ```
+/// use std::sync::Arc;
+/// use tokio::net::UdpSocket;
+/// use masterstat::{HeartbeatOptions, QueryOptions};
+///
+/// async fn test() -> masterstat::Result<()> {
+///     let socket = Arc::new(UdpSocket::bind("0.0.0.0:27500").await?);
+///     let masters = ["master.quakeworld.nu:27000"];
+///     let handle = masterstat::heartbeat_with_socket(
+///         socket.clone(),
+///         &masters,
+///         &HeartbeatOptions::default(),
+///         &QueryOptions::new(),
+///     )?;
+///
+///     // serve game clients using `socket`
+///
+///     handle.stop().await;
+///     Ok(())
+/// }
+/// ```
+pub fn heartbeat_with_socket(
+    socket: Arc<UdpSocket>,
+    master_addresses: &[impl Into<MasterAddress> + Clone],
+    heartbeat_options: &HeartbeatOptions,
+    options: &QueryOptions,
+) -> Result<HeartbeatHandle> {
+    let local_addr = socket.local_addr()?;
+    let interval = heartbeat_options.interval;
+    let resolve_timeout = options.resolve_timeout;
+    let cancel = options.stop();
+    let masters = master_addresses
+        .iter()
+        .cloned()
+        .map(Into::into)
+        .collect::<Vec<MasterAddress>>();
+    let player_count = Arc::<AtomicUsize>::default();
+    let (stop, mut stopped) = oneshot::channel();
+
+    let task = tokio::spawn({
+        let player_count = player_count.clone();
+
+        async move {
+            let mut sequence: u32 = 0;
+
+            loop {
+                sequence = sequence.wrapping_add(1);
+                let players = player_count.load(Ordering::Relaxed);
+                let message = format!("{}\n{}\n{}\n", HEARTBEAT as char, sequence, players);
+                send_to_all(&socket, &masters, message.as_bytes(), resolve_timeout).await;
+
+                match cancel
+                    .run(tokio::time::timeout(interval, &mut stopped))
+                    .await
+                {
+                    Ok(Err(_elapsed)) => {}
+                    _ => break,
+                }
+            }
+
+            let message = format!("{}\n", SHUTDOWN as char);
+            send_to_all(&socket, &masters, message.as_bytes(), resolve_timeout).await;
+        }
+    });
+
+    Ok(HeartbeatHandle {
+        local_addr,
+        player_count,
+        stop,
+        task,
+    })
+}
+
+async fn send_to_all(
+    socket: &UdpSocket,
+    master_addresses: &[MasterAddress],
+    message: &[u8],
+    resolve_timeout: Option<Duration>,
+) {
+    let tasks = master_addresses.iter().map(|master_address| async move {
+        let resolved = udp::resolve_async_within(master_address.as_str(), resolve_timeout).await;
+        if let Ok(socket_address) = resolved {
+            let _ = socket.send_to(message, socket_address).await;
+        }
+    });
+    futures::future::join_all(tasks).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::cancel::CancellationToken;
+    use crate::master_server::MasterServer;
+    use crate::server_address::ServerAddress;
+
+    use super::*;
+
+    async fn wait_for_servers(master: &MasterServer, count: usize) -> Vec<ServerAddress> {
+        for _ in 0..100 {
+            let servers = master.servers();
+            if servers.len() == count {
+                return servers;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        master.servers()
+    }
+
+    #[tokio::test]
+    async fn test_heartbeat() -> Result<()> {
+        let master = Arc::new(MasterServer::bind("127.0.0.1:0").await?);
+        let masters = [master.local_addr()?.to_string()];
+        tokio::spawn({
+            let master = master.clone();
+            async move { master.run().await }
+        });
+
+        let heartbeat_options = HeartbeatOptions {
+            interval: Duration::from_millis(50),
+        };
+        let options = QueryOptions::new().bind_address("127.0.0.1:0".parse().unwrap());
+        let handle = heartbeat(&masters, &heartbeat_options, &options).await?;
+        let local_addr = handle.local_addr();
+        handle.set_player_count(2);
+
+        // registered
+        assert_eq!(
+            wait_for_servers(&master, 1).await,
+            vec![ServerAddress::new(local_addr.ip(), local_addr.port())]
+        );
+
+        // removed on shutdown
+        handle.stop().await;
+        assert_eq!(wait_for_servers(&master, 0).await, vec![]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_heartbeat_cancelled() -> Result<()> {
+        let master = Arc::new(MasterServer::bind("127.0.0.1:0").await?);
+        let masters = [master.local_addr()?.to_string()];
+        tokio::spawn({
+            let master = master.clone();
+            async move { master.run().await }
+        });
+
+        let token = CancellationToken::new();
+        let options = QueryOptions::new()
+            .bind_address("127.0.0.1:0".parse().unwrap())
+            .cancellation_token(token.clone());
+        let _handle = heartbeat(&masters, &HeartbeatOptions::default(), &options).await?;
+        assert_eq!(wait_for_servers(&master, 1).await.len(), 1);
+
+        // shutdown sent once cancelled, without stopping the handle
+        token.cancel();
+        assert_eq!(wait_for_servers(&master, 0).await, vec![]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_heartbeat_with_socket() -> Result<()> {
+        let master = Arc::new(MasterServer::bind("127.0.0.1:0").await?);
+        let masters = [master.local_addr()?.to_string()];
+        tokio::spawn({
+            let master = master.clone();
+            async move { master.run().await }
+        });
+
+        let game_socket = Arc::new(UdpSocket::bind("127.0.0.1:0").await?);
+        let game_address = game_socket.local_addr()?;
+        let handle = heartbeat_with_socket(
+            game_socket.clone(),
+            &masters,
+            &HeartbeatOptions::default(),
+            &QueryOptions::new(),
+        )?;
+
+        // sent from the game port
+        assert_eq!(handle.local_addr(), game_address);
+        assert_eq!(
+            wait_for_servers(&master, 1).await,
+            vec![ServerAddress::new(game_address.ip(), game_address.port())]
+        );
+
+        // socket still usable by the game server
+        handle.stop().await;
+        let client = UdpSocket::bind("127.0.0.1:0").await?;
+        client.send_to(b"ping", game_address).await?;
+        let mut buffer = [0; 16];
+        let (bytes_read, _) = game_socket.recv_from(&mut buffer).await?;
+        assert_eq!(&buffer[..bytes_read], b"ping");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,329 @@
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::error::{Error, Result};
+use crate::protocol::{DarkPlaces, MasterProtocol, Quake2, Quake3, QuakeWorld, Valve};
+
+/// Game (or family of games) listed by master servers
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Game {
+    QuakeWorld,
+    Quake2,
+    Quake3,
+    Xonotic,
+    HalfLife,
+}
+
+impl Game {
+    pub const ALL: [Game; 5] = [
+        Game::QuakeWorld,
+        Game::Quake2,
+        Game::Quake3,
+        Game::Xonotic,
+        Game::HalfLife,
+    ];
+
+    /// Name used in config files, e.g. `quakeworld`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Game::QuakeWorld => "quakeworld",
+            Game::Quake2 => "quake2",
+            Game::Quake3 => "quake3",
+            Game::Xonotic => "xonotic",
+            Game::HalfLife => "halflife",
+        }
+    }
+
+    /// Protocol used to query master servers of the game
+    pub fn protocol(&self) -> Box<dyn MasterProtocol + Send + Sync> {
+        match self {
+            Game::QuakeWorld => Box::new(QuakeWorld),
+            Game::Quake2 => Box::new(Quake2),
+            Game::Quake3 => Box::new(Quake3::new(68)),
+            Game::Xonotic => Box::new(DarkPlaces::new("Xonotic", 3)),
+            Game::HalfLife => Box::new(Valve::new("\\gamedir\\valve")),
+        }
+    }
+}
+
+impl Display for Game {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Game {
+    type Err = ParseGameError;
+
+    fn from_str(name: &str) -> std::result::Result<Self, Self::Err> {
+        Game::ALL
+            .into_iter()
+            .find(|game| game.name().eq_ignore_ascii_case(name))
+            .ok_or_else(|| ParseGameError {
+                name: name.to_string(),
+            })
+    }
+}
+
+/// Error returned when parsing an unknown game name, see [`Game::name`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseGameError {
+    pub name: String,
+}
+
+impl Display for ParseGameError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let names = Game::ALL.map(|game| game.name()).join(", ");
+        write!(f, "unknown game: {} (expected one of {})", self.name, names)
+    }
+}
+
+impl std::error::Error for ParseGameError {}
+
+/// Master server listed in the registry, see [`known_masters`]
+///
+/// Can be passed directly to the query functions, see [`MasterAddress`](crate::MasterAddress).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KnownMaster {
+    address: String,
+    host: String,
+    port: u16,
+    game: Game,
+    notes: String,
+}
+
+impl KnownMaster {
+    pub fn new(host: &str, port: u16, game: Game, notes: &str) -> Self {
+        KnownMaster {
+            address: format!("{}:{}", host, port),
+            host: host.to_string(),
+            port,
+            game,
+            notes: notes.to_string(),
+        }
+    }
+
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    pub fn game(&self) -> Game {
+        self.game
+    }
+
+    pub fn notes(&self) -> &str {
+        &self.notes
+    }
+
+    /// Address as `host:port`
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+}
+
+impl AsRef<str> for KnownMaster {
+    fn as_ref(&self) -> &str {
+        &self.address
+    }
+}
+
+impl Display for KnownMaster {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.address)
+    }
+}
+
+const BUILT_IN: &[(Game, &str, u16, &str)] = &[
+    (
+        Game::QuakeWorld,
+        "master.quakeworld.nu",
+        27000,
+        "QuakeWorld.nu",
+    ),
+    (
+        Game::QuakeWorld,
+        "master.quakeservers.net",
+        27000,
+        "QuakeServers.net",
+    ),
+    (Game::QuakeWorld, "qwmaster.fodquake.net", 27000, "Fodquake"),
+    (
+        Game::Quake2,
+        "master.quakeservers.net",
+        27900,
+        "QuakeServers.net",
+    ),
+    (Game::Quake3, "master.quake3arena.com", 27950, "id Software"),
+    (Game::Quake3, "master.ioquake3.org", 27950, "ioquake3"),
+    (Game::Xonotic, "dpmaster.deathmask.net", 27950, "dpmaster"),
+    (Game::Xonotic, "dpmaster.tchr.no", 27950, "dpmaster"),
+    (Game::HalfLife, "hl1master.steampowered.com", 27011, "Steam"),
+];
+
+/// Built-in master servers of a game
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use masterstat::Game;
+///
+/// async fn test() {
+///     let masters = masterstat::known_masters(Game::QuakeWorld);
+///     let server_addresses = masterstat::server_addresses_from_many(&masters, Some(Duration::from_secs(2))).await;
+/// }
+/// ```
+pub fn known_masters(game: Game) -> Vec<KnownMaster> {
+    BUILT_IN
+        .iter()
+        .filter(|(g, ..)| *g == game)
+        .map(|(g, host, port, notes)| KnownMaster::new(host, *port, *g, notes))
+        .collect()
+}
+
+/// Master servers of a game from a config file, the built-in masters if the file lists none
+///
+/// One master per line as `<game> <host:port> [notes]`, empty lines and lines starting
+/// with `#` are ignored.
+///
+/// ```text
+/// # game      address                  notes
+/// quakeworld  master.quakeworld.nu:27000 QuakeWorld.nu
+/// quake3      master.ioquake3.org:27950
+/// ```
+pub fn known_masters_from_file(path: impl AsRef<Path>, game: Game) -> Result<Vec<KnownMaster>> {
+    let config = std::fs::read_to_string(path)?;
+    let masters = parse_config(&config)?
+        .into_iter()
+        .filter(|m| m.game == game)
+        .collect::<Vec<KnownMaster>>();
+
+    match masters.is_empty() {
+        true => Ok(known_masters(game)),
+        false => Ok(masters),
+    }
+}
+
+fn parse_config(config: &str) -> Result<Vec<KnownMaster>> {
+    config
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(number, line)| {
+            parse_config_line(line).ok_or_else(|| Error::InvalidConfig {
+                line: number,
+                content: line.to_string(),
+            })
+        })
+        .collect()
+}
+
+fn parse_config_line(line: &str) -> Option<KnownMaster> {
+    let (game, rest) = line.split_once(char::is_whitespace)?;
+    let rest = rest.trim_start();
+    let (address, notes) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+    let game = game.parse::<Game>().ok()?;
+    let (host, port) = address.rsplit_once(':')?;
+    let port = port.parse::<u16>().ok()?;
+    let notes = notes.trim();
+
+    match host.is_empty() {
+        true => None,
+        false => Some(KnownMaster::new(host, port, game, notes)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_known_masters() {
+        for game in Game::ALL {
+            let masters = known_masters(game);
+            assert!(!masters.is_empty());
+            assert!(masters.iter().all(|m| m.game() == game));
+        }
+
+        let master = &known_masters(Game::QuakeWorld)[0];
+        assert_eq!(master.as_ref(), "master.quakeworld.nu:27000");
+        assert_eq!(master.host(), "master.quakeworld.nu");
+        assert_eq!(master.port(), 27000);
+    }
+
+    #[test]
+    fn test_game_from_str() {
+        assert_eq!("QuakeWorld".parse::<Game>(), Ok(Game::QuakeWorld));
+        assert_eq!("xonotic".parse::<Game>(), Ok(Game::Xonotic));
+        assert_eq!(
+            "doom".parse::<Game>(),
+            Err(ParseGameError {
+                name: "doom".to_string()
+            })
+        );
+        assert_eq!(
+            "doom".parse::<Game>().unwrap_err().to_string(),
+            "unknown game: doom (expected one of quakeworld, quake2, quake3, xonotic, halflife)"
+        );
+    }
+
+    #[test]
+    fn test_parse_config() {
+        // valid
+        {
+            let config = "# comment\n\nquakeworld  qw.example.com:27000  Example master\nquake3 [::1]:27950\n";
+            assert_eq!(
+                parse_config(config).unwrap(),
+                vec![
+                    KnownMaster::new("qw.example.com", 27000, Game::QuakeWorld, "Example master"),
+                    KnownMaster::new("[::1]", 27950, Game::Quake3, ""),
+                ]
+            );
+        }
+
+        // invalid
+        {
+            let result = parse_config("quakeworld qw.example.com:27000\nquake3 foo\n");
+            assert!(matches!(
+                result,
+                Err(Error::InvalidConfig { line: 2, content }) if content == "quake3 foo"
+            ));
+        }
+    }
+
+    #[test]
+    fn test_known_masters_from_file() -> Result<()> {
+        let path = std::env::temp_dir().join(format!("masterstat-{}.conf", std::process::id()));
+        std::fs::write(&path, "quakeworld qw.example.com:27000\n")?;
+
+        // override
+        assert_eq!(
+            known_masters_from_file(&path, Game::QuakeWorld)?,
+            vec![KnownMaster::new(
+                "qw.example.com",
+                27000,
+                Game::QuakeWorld,
+                ""
+            )]
+        );
+
+        // fallback to built-in
+        assert_eq!(
+            known_masters_from_file(&path, Game::Quake2)?,
+            known_masters(Game::Quake2)
+        );
+
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+}
@@ -1,10 +1,77 @@
 //! # masterstat
 //!
 //! Get server addresses from QuakeWorld master servers.
+//!
+//! Other master server protocols are supported through [`MasterProtocol`],
+//! see the `_with` variants of the query functions. Timeouts, retries, concurrency and more
+//! are configured with [`QueryOptions`], see the `_with_options` variants.
 
+mod cancel;
+mod client;
 mod command;
+mod crawler;
+mod error;
+mod heartbeat;
+mod known_masters;
+mod master_address;
+mod master_event;
+mod master_result;
+mod master_server;
+mod ping;
+mod protocol;
+mod query_options;
+mod retry;
+#[cfg(feature = "serde")]
+pub mod serde_string;
 mod server_address;
+mod server_status;
+mod shared_socket;
+mod stagger;
+#[cfg(test)]
+mod test_util;
+mod udp;
 
+pub use crate::cancel::CancellationToken;
+pub use crate::client::MasterClient;
+pub use crate::command::master_result;
+pub use crate::command::master_result_with;
+pub use crate::command::master_result_with_options;
+pub use crate::command::master_results;
+pub use crate::command::master_results_with;
+pub use crate::command::master_results_with_options;
 pub use crate::command::server_addresses;
+pub use crate::command::server_addresses_async;
+pub use crate::command::server_addresses_async_with;
+pub use crate::command::server_addresses_async_with_options;
 pub use crate::command::server_addresses_from_many;
+pub use crate::command::server_addresses_from_many_with;
+pub use crate::command::server_addresses_from_many_with_options;
+pub use crate::command::server_addresses_with;
+pub use crate::command::server_addresses_with_options;
+pub use crate::crawler::{crawl, LiveServer, Snapshot};
+pub use crate::error::{Error, Result};
+pub use crate::heartbeat::{heartbeat, heartbeat_with_socket, HeartbeatHandle, HeartbeatOptions};
+pub use crate::known_masters::{
+    known_masters, known_masters_from_file, Game, KnownMaster, ParseGameError,
+};
+pub use crate::master_address::MasterAddress;
+pub use crate::master_event::{
+    master_events, master_events_with, master_events_with_options, MasterEvent,
+};
+pub use crate::master_result::{MasterResult, MasterResults};
+pub use crate::master_server::MasterServer;
+pub use crate::ping::{ping, ping_many, sort_by_latency, PingMethod, PingOptions, PingResult};
+pub use crate::protocol::{
+    darkplaces_server_addresses, darkplaces_server_addresses_async, decode_raw_addresses,
+    quake2_server_addresses, quake2_server_addresses_async, quake3_server_addresses,
+    quake3_server_addresses_async, valve_server_addresses, valve_server_addresses_async,
+    DarkPlaces, MasterProtocol, Quake2, Quake3, QuakeWorld, Valve, ValveRegion,
+};
+pub use crate::query_options::{QueryOptions, SourceAddressPolicy};
+pub use crate::retry::RetryPolicy;
 pub use crate::server_address::ServerAddress;
+pub use crate::server_status::{
+    server_status, server_status_async, server_status_async_with_options, server_status_from_many,
+    server_status_from_many_with_options, server_status_with_options, Player, ServerInfo,
+    ServerStatus, ServerStatusResult,
+};
@@ -0,0 +1,197 @@
+use std::fmt::Display;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+
+use crate::known_masters::KnownMaster;
+use crate::server_address::ServerAddress;
+
+/// Address of a master server, accepted by all query functions
+///
+/// Created from `host:port` strings, `(host, port)` tuples, socket addresses, [`ServerAddress`]
+/// and [`KnownMaster`].
+/// Host names are resolved when the master is queried.
+///
+/// # Example
+///
+/// ```
+/// use masterstat::{MasterAddress, ServerAddress};
+///
+/// let by_name = MasterAddress::from("master.quakeworld.nu:27000");
+/// let by_address = MasterAddress::from(ServerAddress::new("10.0.0.1".parse().unwrap(), 27000));
+/// assert_eq!(by_address.as_str(), "10.0.0.1:27000");
+/// ```
+#[derive(Clone, Debug, Hash, PartialOrd, Ord, PartialEq, Eq)]
+pub struct MasterAddress(String);
+
+impl MasterAddress {
+    /// Address as `host:port`
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for MasterAddress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl AsRef<str> for MasterAddress {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for MasterAddress {
+    fn from(address: &str) -> Self {
+        MasterAddress(address.to_string())
+    }
+}
+
+/// For `&master` with `master: &str`, as accepted when query functions took `&str`
+impl From<&&str> for MasterAddress {
+    fn from(address: &&str) -> Self {
+        MasterAddress(address.to_string())
+    }
+}
+
+impl From<String> for MasterAddress {
+    fn from(address: String) -> Self {
+        MasterAddress(address)
+    }
+}
+
+impl From<&String> for MasterAddress {
+    fn from(address: &String) -> Self {
+        MasterAddress(address.clone())
+    }
+}
+
+impl From<(&str, u16)> for MasterAddress {
+    fn from((host, port): (&str, u16)) -> Self {
+        MasterAddress(format!("{}:{}", host, port))
+    }
+}
+
+impl From<(String, u16)> for MasterAddress {
+    fn from((host, port): (String, u16)) -> Self {
+        (host.as_str(), port).into()
+    }
+}
+
+impl From<(IpAddr, u16)> for MasterAddress {
+    fn from((ip, port): (IpAddr, u16)) -> Self {
+        SocketAddr::new(ip, port).into()
+    }
+}
+
+impl From<(Ipv4Addr, u16)> for MasterAddress {
+    fn from((ip, port): (Ipv4Addr, u16)) -> Self {
+        SocketAddrV4::new(ip, port).into()
+    }
+}
+
+impl From<(Ipv6Addr, u16)> for MasterAddress {
+    fn from((ip, port): (Ipv6Addr, u16)) -> Self {
+        SocketAddrV6::new(ip, port, 0, 0).into()
+    }
+}
+
+/// IPv6 addresses are enclosed in brackets (`[ip]:port`)
+impl From<SocketAddr> for MasterAddress {
+    fn from(address: SocketAddr) -> Self {
+        MasterAddress(address.to_string())
+    }
+}
+
+impl From<SocketAddrV4> for MasterAddress {
+    fn from(address: SocketAddrV4) -> Self {
+        SocketAddr::V4(address).into()
+    }
+}
+
+impl From<SocketAddrV6> for MasterAddress {
+    fn from(address: SocketAddrV6) -> Self {
+        SocketAddr::V6(address).into()
+    }
+}
+
+impl From<ServerAddress> for MasterAddress {
+    fn from(address: ServerAddress) -> Self {
+        address.socket_addr().into()
+    }
+}
+
+impl From<&ServerAddress> for MasterAddress {
+    fn from(address: &ServerAddress) -> Self {
+        address.socket_addr().into()
+    }
+}
+
+impl From<KnownMaster> for MasterAddress {
+    fn from(master: KnownMaster) -> Self {
+        master.as_ref().into()
+    }
+}
+
+impl From<&KnownMaster> for MasterAddress {
+    fn from(master: &KnownMaster) -> Self {
+        master.as_ref().into()
+    }
+}
+
+impl From<&MasterAddress> for MasterAddress {
+    fn from(address: &MasterAddress) -> Self {
+        address.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::known_masters::Game;
+
+    use super::*;
+
+    #[test]
+    fn test_from() {
+        assert_eq!(
+            MasterAddress::from("qw.example.com:27000").as_str(),
+            "qw.example.com:27000"
+        );
+        assert_eq!(
+            MasterAddress::from(("qw.example.com", 27000)).as_str(),
+            "qw.example.com:27000"
+        );
+        assert_eq!(
+            MasterAddress::from(("qw.example.com".to_string(), 27000)).as_str(),
+            "qw.example.com:27000"
+        );
+        assert_eq!(
+            MasterAddress::from((Ipv4Addr::LOCALHOST, 27000)).as_str(),
+            "127.0.0.1:27000"
+        );
+        assert_eq!(
+            MasterAddress::from((Ipv6Addr::LOCALHOST, 27950)).as_str(),
+            "[::1]:27950"
+        );
+        assert_eq!(
+            MasterAddress::from("[::1]:27950".parse::<SocketAddr>().unwrap()).as_str(),
+            "[::1]:27950"
+        );
+        assert_eq!(
+            MasterAddress::from(ServerAddress::new([10, 0, 0, 1].into(), 27000)).as_str(),
+            "10.0.0.1:27000"
+        );
+        assert_eq!(
+            MasterAddress::from(&KnownMaster::new(
+                "qw.example.com",
+                27000,
+                Game::QuakeWorld,
+                ""
+            ))
+            .as_str(),
+            "qw.example.com:27000"
+        );
+    }
+}
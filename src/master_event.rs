@@ -0,0 +1,217 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::channel::mpsc;
+use futures::{Stream, StreamExt};
+
+use crate::cancel::Stop;
+use crate::command::query_master;
+use crate::master_address::MasterAddress;
+use crate::master_result::MasterResult;
+use crate::protocol::{MasterProtocol, QuakeWorld};
+use crate::query_options::QueryOptions;
+use crate::server_address::ServerAddress;
+use crate::stagger::Stagger;
+
+/// Progress of querying master servers, see [`master_events`]
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum MasterEvent {
+    /// The master is about to be queried
+    Started { master_address: String },
+    /// Server addresses of a response packet, as received (not sorted or unique)
+    Addresses {
+        master_address: String,
+        server_addresses: Vec<ServerAddress>,
+    },
+    /// The master was queried successfully
+    Finished(MasterResult),
+    /// The master could not be queried, addresses received before the error were already sent
+    Failed(MasterResult),
+}
+
+/// Query many QuakeWorld master servers, yielding events as responses arrive (async, in parallel)
+///
+/// Events of each master are in order (`Started`, any number of `Addresses`, `Finished` or `Failed`),
+/// events of different masters are interleaved.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use futures::StreamExt;
+/// use masterstat::MasterEvent;
+///
+/// async fn test() {
+///     let masters = ["master.quakeworld.nu:27000", "master.quakeservers.net:27000"];
+///     let mut events = masterstat::master_events(&masters, Some(Duration::from_secs(2)));
+///
+///     while let Some(event) = events.next().await {
+///         match event {
+///             MasterEvent::Addresses { server_addresses, .. } => println!("+{}", server_addresses.len()),
+///             MasterEvent::Failed(result) => eprintln!("{}: {:?}", result.master_address, result.outcome),
+///             _ => {}
+///         }
+///     }
+/// }
+/// ```
+pub fn master_events<'a>(
+    master_addresses: &[impl Into<MasterAddress> + Clone],
+    timeout: Option<Duration>,
+) -> impl Stream<Item = MasterEvent> + 'a {
+    master_events_with(master_addresses, &QuakeWorld, timeout)
+}
+
+/// Query many master servers using the given protocol, yielding events as responses arrive (async, in parallel)
+pub fn master_events_with<'a>(
+    master_addresses: &[impl Into<MasterAddress> + Clone],
+    protocol: &'a (impl MasterProtocol + ?Sized),
+    timeout: Option<Duration>,
+) -> impl Stream<Item = MasterEvent> + 'a {
+    let options = QueryOptions::new().timeout(timeout);
+    master_events_with_options(master_addresses, protocol, &options)
+}
+
+/// Query many master servers using the given protocol and options, yielding events as responses
+/// arrive (async, in parallel)
+pub fn master_events_with_options<'a>(
+    master_addresses: &[impl Into<MasterAddress> + Clone],
+    protocol: &'a (impl MasterProtocol + ?Sized),
+    options: &QueryOptions,
+) -> impl Stream<Item = MasterEvent> + 'a {
+    indexed_master_events(master_addresses, protocol, options).map(|(_, event)| event)
+}
+
+/// Events paired with the index of the master they belong to
+pub(crate) fn indexed_master_events<'a>(
+    master_addresses: &[impl Into<MasterAddress> + Clone],
+    protocol: &'a (impl MasterProtocol + ?Sized),
+    options: &QueryOptions,
+) -> impl Stream<Item = (usize, MasterEvent)> + 'a {
+    let concurrency = options.concurrency;
+    let options = options.clone();
+    let stop = options.stop();
+    let stagger = Arc::new(Stagger::new(options.stagger));
+    let streams = master_addresses
+        .iter()
+        .cloned()
+        .map(Into::into)
+        .collect::<Vec<MasterAddress>>()
+        .into_iter()
+        .enumerate()
+        .map(move |(index, master_address)| {
+            let events = single_master_events(
+                master_address,
+                protocol,
+                options.clone(),
+                stop.clone(),
+                stagger.clone(),
+            );
+            Box::pin(events.map(move |event| (index, event)))
+        });
+    futures::stream::iter(streams).flatten_unordered(concurrency)
+}
+
+fn single_master_events<'a>(
+    master_address: MasterAddress,
+    protocol: &'a (impl MasterProtocol + ?Sized),
+    options: QueryOptions,
+    stop: Stop,
+    stagger: Arc<Stagger>,
+) -> impl Stream<Item = MasterEvent> + 'a {
+    let (sender, receiver) = mpsc::unbounded();
+
+    // events are sent through the channel to keep them in order,
+    // the query itself only needs to be polled until it completes
+    let query = async move {
+        let _ = stop.run(stagger.wait()).await;
+        let _ = sender.unbounded_send(MasterEvent::Started {
+            master_address: master_address.to_string(),
+        });
+        let result = query_master(
+            master_address.as_str(),
+            protocol,
+            &options,
+            &stop,
+            |entries| {
+                let _ = sender.unbounded_send(MasterEvent::Addresses {
+                    master_address: master_address.to_string(),
+                    server_addresses: entries.to_vec(),
+                });
+            },
+        )
+        .await;
+        let event = match result.is_ok() {
+            true => MasterEvent::Finished(result),
+            false => MasterEvent::Failed(result),
+        };
+        let _ = sender.unbounded_send(event);
+    };
+
+    futures::stream::select(
+        receiver,
+        futures::stream::once(query).filter_map(|_| async { None }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::error::Error;
+    use crate::test_util::spawn_responder;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_master_events() {
+        let responding = spawn_responder(vec![
+            vec![
+                0xff, 0xff, 0xff, 0xff, 0x64, 0x0a, 192, 168, 1, 2, 0x75, 0x30,
+            ],
+            vec![
+                0xff, 0xff, 0xff, 0xff, 0x64, 0x0a, 192, 168, 1, 1, 0x75, 0x30,
+            ],
+        ]);
+        let silent = spawn_responder(vec![]);
+        let masters = [responding.to_string(), silent.to_string()];
+        let events = master_events(&masters, Some(Duration::from_millis(200)))
+            .collect::<Vec<MasterEvent>>()
+            .await;
+
+        let summary = |master: &str| {
+            events
+                .iter()
+                .filter_map(|event| match event {
+                    MasterEvent::Started { master_address } if master_address == master => {
+                        Some("started".to_string())
+                    }
+                    MasterEvent::Addresses {
+                        master_address,
+                        server_addresses,
+                    } if master_address == master => Some(format!("{:?}", server_addresses)),
+                    MasterEvent::Finished(result) if result.master_address == master => {
+                        Some("finished".to_string())
+                    }
+                    MasterEvent::Failed(result) if result.master_address == master => {
+                        assert!(matches!(result.outcome, Err(Error::Timeout)));
+                        Some("failed".to_string())
+                    }
+                    _ => None,
+                })
+                .collect::<Vec<String>>()
+        };
+
+        assert_eq!(events.len(), 6);
+        assert_eq!(
+            summary(&masters[0]),
+            vec![
+                "started",
+                "[ServerAddress { ip: 192.168.1.2, port: 30000 }]",
+                "[ServerAddress { ip: 192.168.1.1, port: 30000 }]",
+                "finished",
+            ]
+        );
+        assert_eq!(summary(&masters[1]), vec!["started", "failed"]);
+    }
+}
@@ -0,0 +1,155 @@
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use crate::command::{incomplete_as_error, sorted_and_unique};
+use crate::error::Result;
+use crate::server_address::ServerAddress;
+
+/// Outcome of querying a single master server
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct MasterResult {
+    /// Master address as given by the caller
+    pub master_address: String,
+    /// Resolved socket address, `None` if resolving failed
+    pub socket_address: Option<SocketAddr>,
+    /// Server addresses (sorted and unique) or the error that occurred
+    pub outcome: Result<Vec<ServerAddress>>,
+    /// Time until the first response packet arrived
+    pub rtt: Option<Duration>,
+    /// Number of response packets received
+    pub packet_count: usize,
+    /// Number of attempts made, 0 if resolving the address failed
+    pub attempts: u32,
+    /// Whether the whole list was received
+    ///
+    /// False if a paging master stopped answering before the last page, or the deadline or
+    /// cancellation stopped the query. The addresses received so far are in `outcome`.
+    pub complete: bool,
+}
+
+impl MasterResult {
+    pub fn is_ok(&self) -> bool {
+        self.outcome.is_ok()
+    }
+
+    /// Server addresses, [`Error::Incomplete`](crate::Error::Incomplete) if the list is not complete
+    pub fn into_outcome(self) -> Result<Vec<ServerAddress>> {
+        incomplete_as_error(self.outcome, self.complete)
+    }
+}
+
+/// Outcome of querying many master servers
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct MasterResults {
+    /// One result per master, in the order the masters were given
+    pub results: Vec<MasterResult>,
+}
+
+impl MasterResults {
+    /// Server addresses from all successful masters (sorted and unique)
+    pub fn server_addresses(&self) -> Vec<ServerAddress> {
+        let server_addresses = self
+            .results
+            .iter()
+            .filter_map(|r| r.outcome.as_ref().ok())
+            .flatten()
+            .cloned()
+            .collect::<Vec<ServerAddress>>();
+        sorted_and_unique(&server_addresses)
+    }
+
+    /// Results of masters that failed
+    pub fn errors(&self) -> impl Iterator<Item = &MasterResult> {
+        self.results.iter().filter(|r| !r.is_ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::error::Error;
+
+    use super::*;
+
+    fn master_result(outcome: Result<Vec<ServerAddress>>) -> MasterResult {
+        MasterResult {
+            master_address: "localhost:27000".to_string(),
+            socket_address: None,
+            outcome,
+            rtt: None,
+            packet_count: 0,
+            attempts: 1,
+            complete: true,
+        }
+    }
+
+    #[test]
+    fn test_master_results() {
+        let server1 = ServerAddress::new("192.168.1.1".parse().unwrap(), 1);
+        let server2 = ServerAddress::new("192.168.1.2".parse().unwrap(), 1);
+        let results = MasterResults {
+            results: vec![
+                master_result(Ok(vec![server2.clone(), server1.clone()])),
+                master_result(Err(Error::Timeout)),
+                master_result(Ok(vec![server1.clone()])),
+            ],
+        };
+        assert_eq!(results.server_addresses(), vec![server1, server2]);
+        assert_eq!(results.errors().count(), 1);
+    }
+
+    #[test]
+    fn test_into_outcome() {
+        let server = ServerAddress::new("192.168.1.1".parse().unwrap(), 1);
+        let complete = master_result(Ok(vec![server.clone()]));
+        assert_eq!(complete.into_outcome().unwrap(), vec![server.clone()]);
+
+        let incomplete = MasterResult {
+            complete: false,
+            ..master_result(Ok(vec![server.clone()]))
+        };
+        assert!(matches!(
+            incomplete.into_outcome(),
+            Err(Error::Incomplete { server_addresses }) if server_addresses == vec![server]
+        ));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize() -> anyhow::Result<()> {
+        let server = ServerAddress::new("192.168.1.1".parse()?, 1);
+        let results = MasterResults {
+            results: vec![
+                master_result(Ok(vec![server])),
+                master_result(Err(Error::Timeout)),
+            ],
+        };
+        assert_eq!(
+            serde_json::to_value(&results)?["results"],
+            serde_json::json!([
+                {
+                    "master_address": "localhost:27000",
+                    "socket_address": null,
+                    "outcome": { "Ok": [{ "ip": "192.168.1.1", "port": 1 }] },
+                    "rtt": null,
+                    "packet_count": 0,
+                    "attempts": 1,
+                    "complete": true
+                },
+                {
+                    "master_address": "localhost:27000",
+                    "socket_address": null,
+                    "outcome": { "Err": "timed out waiting for response" },
+                    "rtt": null,
+                    "packet_count": 0,
+                    "attempts": 1,
+                    "complete": true
+                }
+            ])
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use tokio::net::{ToSocketAddrs, UdpSocket};
+
+use crate::command::sorted_and_unique;
+use crate::error::Result;
+use crate::protocol::{HEARTBEAT, SERVERS, SERVERS_RESPONSE_HEADER, SHUTDOWN};
+use crate::server_address::{ServerAddress, RAW_ADDRESS_SIZE};
+use crate::udp;
+
+/// Time after which a server that stopped sending heartbeats is removed from the list
+pub const DEFAULT_EXPIRY: Duration = Duration::from_secs(15 * 60);
+
+const OUT_OF_BAND_PREFIX: [u8; 4] = [0xff, 0xff, 0xff, 0xff];
+const MAX_ENTRIES_PER_PACKET: usize = 232; // fits in 1400 bytes
+const BUFFER_SIZE: usize = 2048;
+
+/// QuakeWorld master server
+///
+/// Keeps a list of game servers that send heartbeats (`a`) and removes them on shutdown (`C`)
+/// or when they stop sending heartbeats. Replies to `c` queries with the list in the format
+/// read by [`server_addresses`](crate::server_addresses).
+///
+/// Only IPv4 servers are listed, the QuakeWorld list format can not represent IPv6 addresses.
+///
+/// # Example
+///
+/// ```
+/// use std::sync::Arc;
+/// use masterstat::MasterServer;
+///
+/// async fn test() -> masterstat::Result<()> {
+///     let master = Arc::new(MasterServer::bind("0.0.0.0:27000").await?);
+///     tokio::spawn({
+///         let master = master.clone();
+///         async move { master.run().await }
+///     });
+///
+///     println!("{} servers registered", master.servers().len());
+///     Ok(())
+/// }
+/// ```
+pub struct MasterServer {
+    socket: UdpSocket,
+    expiry: Duration,
+    servers: Mutex<HashMap<ServerAddress, Instant>>,
+}
+
+impl MasterServer {
+    /// Bind a master server to the given local address
+    pub async fn bind(address: impl ToSocketAddrs) -> Result<Self> {
+        Ok(MasterServer {
+            socket: UdpSocket::bind(address).await?,
+            expiry: DEFAULT_EXPIRY,
+            servers: Mutex::default(),
+        })
+    }
+
+    /// Set the time after which servers without heartbeats are removed
+    pub fn with_expiry(mut self, expiry: Duration) -> Self {
+        self.expiry = expiry;
+        self
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.socket.local_addr()?)
+    }
+
+    /// Currently registered servers (sorted and unique)
+    pub fn servers(&self) -> Vec<ServerAddress> {
+        self.active_servers(Instant::now())
+    }
+
+    /// Receive and answer packets until the socket fails
+    ///
+    /// Errors concerning a single packet, e.g. a reply that could not be sent or a connection
+    /// reset reported for an earlier reply, are ignored.
+    pub async fn run(&self) -> Result<()> {
+        let mut buffer = vec![0; BUFFER_SIZE];
+
+        loop {
+            let (bytes_read, source) = match self.socket.recv_from(&mut buffer).await {
+                Ok(received) => received,
+                Err(e) if udp::is_transient(&e) => continue,
+                Err(e) => return Err(e.into()),
+            };
+
+            for packet in self.handle_packet(&buffer[..bytes_read], source, Instant::now()) {
+                let _ = self.socket.send_to(&packet, source).await;
+            }
+        }
+    }
+
+    /// Update the list from a received packet, returns packets to send back to the source
+    fn handle_packet(&self, packet: &[u8], source: SocketAddr, now: Instant) -> Vec<Vec<u8>> {
+        let packet = packet.strip_prefix(&OUT_OF_BAND_PREFIX).unwrap_or(packet);
+        let server = ServerAddress::from(source);
+
+        match packet.first() {
+            Some(&HEARTBEAT) if source.is_ipv4() => {
+                self.servers.lock().unwrap().insert(server, now);
+                vec![]
+            }
+            Some(&SHUTDOWN) => {
+                self.servers.lock().unwrap().remove(&server);
+                vec![]
+            }
+            Some(&SERVERS) => servers_response(&self.active_servers(now)),
+            _ => vec![],
+        }
+    }
+
+    fn active_servers(&self, now: Instant) -> Vec<ServerAddress> {
+        let mut servers = self.servers.lock().unwrap();
+        servers.retain(|_, last_heartbeat| now.duration_since(*last_heartbeat) < self.expiry);
+        sorted_and_unique(&servers.keys().cloned().collect::<Vec<ServerAddress>>())
+    }
+}
+
+/// Encode servers as response packets, each with header and at most [`MAX_ENTRIES_PER_PACKET`] entries
+fn servers_response(servers: &[ServerAddress]) -> Vec<Vec<u8>> {
+    let entries = servers
+        .iter()
+        .filter_map(|server| match server.ip {
+            IpAddr::V4(ip) => Some([ip.octets().as_slice(), &server.port.to_be_bytes()].concat()),
+            IpAddr::V6(_) => None,
+        })
+        .collect::<Vec<Vec<u8>>>();
+
+    if entries.is_empty() {
+        return vec![SERVERS_RESPONSE_HEADER.to_vec()];
+    }
+
+    entries
+        .chunks(MAX_ENTRIES_PER_PACKET)
+        .map(|chunk| {
+            let mut packet = Vec::with_capacity(6 + chunk.len() * RAW_ADDRESS_SIZE);
+            packet.extend_from_slice(&SERVERS_RESPONSE_HEADER);
+            packet.extend(chunk.iter().flatten());
+            packet
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_handle_packet() -> Result<()> {
+        let master = MasterServer::bind("127.0.0.1:0")
+            .await?
+            .with_expiry(Duration::from_secs(60));
+        let server1: SocketAddr = "10.0.0.1:27500".parse().unwrap();
+        let server2: SocketAddr = "10.0.0.2:27500".parse().unwrap();
+        let client: SocketAddr = "10.0.0.3:1234".parse().unwrap();
+        let now = Instant::now();
+
+        // heartbeats
+        assert!(master.handle_packet(b"a\n1\n0\n", server1, now).is_empty());
+        assert!(master.handle_packet(b"a\n1\n4\n", server2, now).is_empty());
+        assert_eq!(
+            master.handle_packet(b"c\n\0", client, now),
+            vec![vec![
+                0xff, 0xff, 0xff, 0xff, 0x64, 0x0a, 10, 0, 0, 1, 0x6b, 0x6c, 10, 0, 0, 2, 0x6b,
+                0x6c,
+            ]]
+        );
+
+        // shutdown
+        master.handle_packet(b"C\n", server1, now);
+        assert_eq!(master.active_servers(now).len(), 1);
+
+        // expiry
+        master.handle_packet(b"a\n2\n0\n", server1, now + Duration::from_secs(30));
+        assert_eq!(
+            master.active_servers(now + Duration::from_secs(70)),
+            vec![ServerAddress::new(server1.ip(), server1.port())]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_servers_response() {
+        // empty list
+        assert_eq!(
+            servers_response(&[]),
+            vec![SERVERS_RESPONSE_HEADER.to_vec()]
+        );
+
+        // split into packets
+        let servers = (0..300)
+            .map(|i| ServerAddress::new([10, 0, (i / 256) as u8, i as u8].into(), 27500))
+            .collect::<Vec<ServerAddress>>();
+        let packets = servers_response(&servers);
+        assert_eq!(packets.len(), 2);
+        assert_eq!(packets[0].len(), 6 + MAX_ENTRIES_PER_PACKET * 6);
+        assert_eq!(packets[1].len(), 6 + (300 - MAX_ENTRIES_PER_PACKET) * 6);
+    }
+
+    #[tokio::test]
+    async fn test_run() -> Result<()> {
+        let master = std::sync::Arc::new(MasterServer::bind("127.0.0.1:0").await?);
+        let master_address = master.local_addr()?;
+        tokio::spawn({
+            let master = master.clone();
+            async move { master.run().await }
+        });
+
+        let server = UdpSocket::bind("127.0.0.1:0").await?;
+        server.send_to(b"a\n1\n0\n", master_address).await?;
+
+        let result = crate::server_addresses_async(
+            &master_address.to_string(),
+            Some(Duration::from_secs(1)),
+        )
+        .await?;
+        let server_address = server.local_addr()?;
+        assert_eq!(
+            result,
+            vec![ServerAddress::new(
+                server_address.ip(),
+                server_address.port()
+            )]
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,327 @@
+use std::time::Duration;
+
+use crate::cancel::Stop;
+use crate::error::{Error, Result};
+use crate::query_options::QueryOptions;
+use crate::server_address::ServerAddress;
+use crate::server_status::server_status_result;
+use crate::stagger::query_each;
+use crate::udp;
+use crate::udp::Next;
+
+/// Time to wait for each reply if the query options have no timeout
+pub const DEFAULT_PING_TIMEOUT: Duration = Duration::from_secs(1);
+
+const PING_COMMAND: &[u8] = b"\xff\xff\xff\xffk\n";
+const ACK_RESPONSE_HEADER: &[u8] = b"\xff\xff\xff\xffl";
+
+/// How round-trip time is measured
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PingMethod {
+    /// Connectionless ping (`k`), answered with an ack (`l`)
+    Ack,
+    /// Status request, for servers that do not answer pings
+    Status,
+    /// Ack, falling back to status if no ack was received
+    AckOrStatus,
+}
+
+/// Sampling settings for measuring round-trip time to servers
+///
+/// Timeout, concurrency, stagger, deadline and cancellation are taken from [`QueryOptions`].
+#[derive(Clone, Debug)]
+pub struct PingOptions {
+    pub method: PingMethod,
+    /// Number of samples per server
+    pub samples: usize,
+    /// Time between samples to the same server
+    pub interval: Duration,
+}
+
+impl Default for PingOptions {
+    fn default() -> Self {
+        PingOptions {
+            method: PingMethod::AckOrStatus,
+            samples: 3,
+            interval: Duration::from_millis(100),
+        }
+    }
+}
+
+/// Round-trip times to a single server
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PingResult {
+    pub address: ServerAddress,
+    /// Method used for the samples ([`PingMethod::Ack`] or [`PingMethod::Status`])
+    pub method: PingMethod,
+    /// Round-trip time of each sample, `None` if lost
+    pub samples: Vec<Option<Duration>>,
+}
+
+impl PingResult {
+    fn received(&self) -> impl Iterator<Item = Duration> + '_ {
+        self.samples.iter().flatten().copied()
+    }
+
+    pub fn is_reachable(&self) -> bool {
+        self.received().next().is_some()
+    }
+
+    pub fn min(&self) -> Option<Duration> {
+        self.received().min()
+    }
+
+    pub fn max(&self) -> Option<Duration> {
+        self.received().max()
+    }
+
+    /// Average round-trip time of received samples
+    pub fn avg(&self) -> Option<Duration> {
+        let received = self.received().collect::<Vec<Duration>>();
+        let total = received.iter().sum::<Duration>();
+        (!received.is_empty()).then(|| total / received.len() as u32)
+    }
+
+    /// Percentage of lost samples (0-100)
+    pub fn loss(&self) -> f32 {
+        if self.samples.is_empty() {
+            return 100.0;
+        }
+
+        let lost = self.samples.iter().filter(|s| s.is_none()).count();
+        100.0 * lost as f32 / self.samples.len() as f32
+    }
+}
+
+/// Sort results by average round-trip time, unreachable servers last
+pub fn sort_by_latency(results: &mut [PingResult]) {
+    results.sort_by_key(|r| (r.avg().is_none(), r.avg()));
+}
+
+/// Measure round-trip time to a single server (async)
+///
+/// Each reply is waited for until the timeout of the options (1 second if not set).
+/// Samples are no longer taken once the deadline passes or the query is cancelled.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use masterstat::{PingOptions, QueryOptions, ServerAddress};
+///
+/// async fn test() {
+///     let address = ServerAddress::new("127.0.0.1".parse().unwrap(), 27500);
+///     let options = QueryOptions::new().timeout(Duration::from_millis(500));
+///     let result = masterstat::ping(&address, &PingOptions::default(), &options).await;
+///     println!("{}: {:?} avg, {}% loss", result.address, result.avg(), result.loss());
+/// }
+/// ```
+pub async fn ping(
+    address: &ServerAddress,
+    ping_options: &PingOptions,
+    options: &QueryOptions,
+) -> PingResult {
+    let options = with_ping_timeout(options);
+    ping_until(address, ping_options, &options, &options.stop()).await
+}
+
+async fn ping_until(
+    address: &ServerAddress,
+    ping_options: &PingOptions,
+    options: &QueryOptions,
+    stop: &Stop,
+) -> PingResult {
+    let method = match ping_options.method {
+        PingMethod::AckOrStatus => PingMethod::Ack,
+        method => method,
+    };
+    let mut result = PingResult {
+        address: address.clone(),
+        method,
+        samples: sample(address, method, ping_options, options, stop).await,
+    };
+
+    if ping_options.method == PingMethod::AckOrStatus && !result.is_reachable() {
+        result.method = PingMethod::Status;
+        result.samples = sample(address, PingMethod::Status, ping_options, options, stop).await;
+    }
+
+    result
+}
+
+/// Measure round-trip time to many servers, results are in the same order as the addresses (async)
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use masterstat::{PingOptions, QueryOptions};
+///
+/// async fn test() {
+///     let masters = ["master.quakeworld.nu:27000", "master.quakeservers.net:27000"];
+///     let server_addresses = masterstat::server_addresses_from_many(&masters, Some(Duration::from_secs(2))).await;
+///
+///     let options = QueryOptions::new().timeout(Duration::from_secs(1)).concurrency(64);
+///     let mut results = masterstat::ping_many(&server_addresses, &PingOptions::default(), &options).await;
+///     masterstat::sort_by_latency(&mut results);
+/// }
+/// ```
+pub async fn ping_many(
+    addresses: &[ServerAddress],
+    ping_options: &PingOptions,
+    options: &QueryOptions,
+) -> Vec<PingResult> {
+    let options = with_ping_timeout(options);
+    let stop = options.stop();
+    query_each(addresses, &options, &stop, |address| {
+        ping_until(address, ping_options, &options, &stop)
+    })
+    .await
+}
+
+/// Options with the default ping timeout if no timeout is set
+fn with_ping_timeout(options: &QueryOptions) -> QueryOptions {
+    let timeout = options.timeout.unwrap_or(DEFAULT_PING_TIMEOUT);
+    options.clone().timeout(timeout)
+}
+
+/// Round-trip time samples, stops taking samples once `stop` is reached
+async fn sample(
+    address: &ServerAddress,
+    method: PingMethod,
+    ping_options: &PingOptions,
+    options: &QueryOptions,
+    stop: &Stop,
+) -> Vec<Option<Duration>> {
+    let mut samples = Vec::with_capacity(ping_options.samples);
+
+    for i in 0..ping_options.samples {
+        let rtt = stop
+            .run(async {
+                if i > 0 {
+                    tokio::time::sleep(ping_options.interval).await;
+                }
+
+                match method {
+                    PingMethod::Status => server_status_result(address, options).await.rtt,
+                    _ => ping_ack(address, options).await.ok(),
+                }
+            })
+            .await;
+
+        match rtt {
+            Ok(rtt) => samples.push(rtt),
+            Err(_) => break,
+        }
+    }
+
+    samples
+}
+
+async fn ping_ack(address: &ServerAddress, options: &QueryOptions) -> Result<Duration> {
+    let response =
+        udp::exchange_async(address.socket_addr(), PING_COMMAND, options, |_| Next::Done).await?;
+
+    match response.packets[0].starts_with(ACK_RESPONSE_HEADER) {
+        true => Ok(response.rtt),
+        false => Err(Error::InvalidHeader {
+            packet: response.packets[0].clone(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::test_util::spawn_handler;
+
+    use super::*;
+
+    fn ping_result(samples: Vec<Option<u64>>) -> PingResult {
+        PingResult {
+            address: ServerAddress::new("10.0.0.1".parse().unwrap(), 27500),
+            method: PingMethod::Ack,
+            samples: samples
+                .into_iter()
+                .map(|s| s.map(Duration::from_millis))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_ping_result() {
+        let result = ping_result(vec![Some(10), None, Some(30), Some(20)]);
+        assert_eq!(result.min(), Some(Duration::from_millis(10)));
+        assert_eq!(result.max(), Some(Duration::from_millis(30)));
+        assert_eq!(result.avg(), Some(Duration::from_millis(20)));
+        assert_eq!(result.loss(), 25.0);
+        assert!(result.is_reachable());
+
+        let result = ping_result(vec![None, None]);
+        assert_eq!(result.avg(), None);
+        assert_eq!(result.loss(), 100.0);
+        assert!(!result.is_reachable());
+    }
+
+    #[test]
+    fn test_sort_by_latency() {
+        let mut results = vec![
+            ping_result(vec![None]),
+            ping_result(vec![Some(30)]),
+            ping_result(vec![Some(10)]),
+        ];
+        sort_by_latency(&mut results);
+        assert_eq!(
+            results.iter().map(|r| r.avg()).collect::<Vec<_>>(),
+            vec![
+                Some(Duration::from_millis(10)),
+                Some(Duration::from_millis(30)),
+                None
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ping_many() {
+        let acking = spawn_handler(|request| match request {
+            PING_COMMAND => vec![b"\xff\xff\xff\xffl".to_vec()],
+            _ => vec![],
+        });
+        let status_only = spawn_handler(|request| match request {
+            PING_COMMAND => vec![],
+            _ => vec![b"\xff\xff\xff\xffn\\map\\dm2\n".to_vec()],
+        });
+        let addresses = [
+            ServerAddress::new(acking.ip(), acking.port()),
+            ServerAddress::new(status_only.ip(), status_only.port()),
+        ];
+        let ping_options = PingOptions {
+            samples: 2,
+            interval: Duration::ZERO,
+            ..Default::default()
+        };
+        let options = QueryOptions::new().timeout(Duration::from_millis(100));
+        let results = ping_many(&addresses, &ping_options, &options).await;
+
+        assert_eq!(results[0].address, addresses[0]);
+        assert_eq!(results[0].method, PingMethod::Ack);
+        assert_eq!(results[0].loss(), 0.0);
+
+        assert_eq!(results[1].address, addresses[1]);
+        assert_eq!(results[1].method, PingMethod::Status);
+        assert_eq!(results[1].loss(), 0.0);
+
+        // no samples taken after the deadline
+        let ping_options = PingOptions {
+            samples: 100,
+            interval: Duration::from_millis(10),
+            ..Default::default()
+        };
+        let options = options.deadline(Duration::from_millis(100));
+        let results = ping_many(&addresses[..1], &ping_options, &options).await;
+        assert!(!results[0].samples.is_empty() && results[0].samples.len() < 100);
+    }
+}
@@ -0,0 +1,135 @@
+use std::time::Duration;
+
+use crate::command::{server_addresses_async_with, server_addresses_with};
+use crate::error::Result;
+use crate::master_address::MasterAddress;
+use crate::protocol::quake3::{decode_getservers_entries, filter_flags, is_last_getservers_packet};
+use crate::protocol::MasterProtocol;
+use crate::server_address::ServerAddress;
+
+const GETSERVERSEXT_COMMAND: &[u8] = b"\xff\xff\xff\xffgetserversExt ";
+const GETSERVERSEXT_RESPONSE_HEADER: &[u8] = b"\xff\xff\xff\xffgetserversExtResponse";
+
+/// DarkPlaces (dpmaster) `getserversExt` protocol
+///
+/// Replies include both IPv4 and IPv6 servers, e.g. game `Xonotic` with protocol `3`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DarkPlaces {
+    /// Game name, e.g. `Xonotic`
+    pub game: String,
+    /// Game protocol number
+    pub protocol: u32,
+    /// Include servers without players
+    pub empty: bool,
+    /// Include servers that are full
+    pub full: bool,
+}
+
+impl DarkPlaces {
+    /// Request all servers (empty and full) of the given game and protocol
+    pub fn new(game: &str, protocol: u32) -> Self {
+        DarkPlaces {
+            game: game.to_string(),
+            protocol,
+            empty: true,
+            full: true,
+        }
+    }
+}
+
+impl MasterProtocol for DarkPlaces {
+    fn request(&self) -> Vec<u8> {
+        let mut request = GETSERVERSEXT_COMMAND.to_vec();
+        request.extend_from_slice(format!("{} {}", self.game, self.protocol).as_bytes());
+        request.extend_from_slice(&filter_flags(self.empty, self.full));
+        request
+    }
+
+    fn response_header(&self) -> &[u8] {
+        GETSERVERSEXT_RESPONSE_HEADER
+    }
+
+    fn decode_entries(&self, body: &[u8]) -> Result<Vec<ServerAddress>> {
+        decode_getservers_entries(body)
+    }
+
+    fn is_last_packet(&self, body: &[u8]) -> bool {
+        is_last_getservers_packet(body)
+    }
+}
+
+/// Get server addresses (IPv4 and IPv6) from a single DarkPlaces (dpmaster) master server
+///
+/// Same as [`server_addresses_with`](crate::server_addresses_with) with [`DarkPlaces`].
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use masterstat::DarkPlaces;
+///
+/// let master = "dpmaster.deathmask.net:27950";
+/// let timeout = Some(Duration::from_secs(2));
+/// match masterstat::darkplaces_server_addresses(&master, &DarkPlaces::new("Xonotic", 3), timeout) {
+///     Ok(addresses) => { println!("found {} server addresses", addresses.len()) },
+///     Err(e) => { eprintln!("error: {}", e); }
+/// }
+/// ```
+pub fn darkplaces_server_addresses(
+    master_address: impl Into<MasterAddress>,
+    query: &DarkPlaces,
+    timeout: Option<Duration>,
+) -> Result<Vec<ServerAddress>> {
+    server_addresses_with(master_address, query, timeout)
+}
+
+/// Get server addresses (IPv4 and IPv6) from a single DarkPlaces (dpmaster) master server (async)
+///
+/// Same as [`server_addresses_async_with`](crate::server_addresses_async_with) with [`DarkPlaces`].
+pub async fn darkplaces_server_addresses_async(
+    master_address: impl Into<MasterAddress>,
+    query: &DarkPlaces,
+    timeout: Option<Duration>,
+) -> Result<Vec<ServerAddress>> {
+    server_addresses_async_with(master_address, query, timeout).await
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_request() {
+        assert_eq!(
+            DarkPlaces::new("Xonotic", 3).request(),
+            b"\xff\xff\xff\xffgetserversExt Xonotic 3 empty full".to_vec()
+        );
+    }
+
+    #[test]
+    fn test_decode_entries() -> Result<()> {
+        let ipv6_entry = [
+            0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0x6d, 0x38,
+        ];
+        let packet = [
+            GETSERVERSEXT_RESPONSE_HEADER,
+            b"/",
+            &ipv6_entry,
+            b"\\",
+            &[192, 168, 1, 1, 0x6d, 0x38],
+            b"\\EOT\0\0\0",
+        ]
+        .concat();
+        let protocol = DarkPlaces::new("Xonotic", 3);
+        let body = protocol.strip_header(&packet)?;
+        assert!(protocol.is_last_packet(body));
+        let result = protocol.decode_entries(body)?;
+        assert_eq!(
+            result.iter().map(|a| a.to_string()).collect::<Vec<_>>(),
+            vec!["[2001:db8::1]:27960", "192.168.1.1:27960"]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,123 @@
+use zerocopy::FromBytes;
+
+use crate::error::{Error, Result};
+use crate::server_address::{RawServerAddress, ServerAddress, RAW_ADDRESS_SIZE};
+
+pub use self::darkplaces::{
+    darkplaces_server_addresses, darkplaces_server_addresses_async, DarkPlaces,
+};
+pub use self::quake2::{quake2_server_addresses, quake2_server_addresses_async, Quake2};
+pub use self::quake3::{quake3_server_addresses, quake3_server_addresses_async, Quake3};
+pub use self::quakeworld::QuakeWorld;
+pub(crate) use self::quakeworld::{HEARTBEAT, SERVERS, SERVERS_RESPONSE_HEADER, SHUTDOWN};
+pub use self::valve::{valve_server_addresses, valve_server_addresses_async, Valve, ValveRegion};
+
+mod darkplaces;
+mod quake2;
+mod quake3;
+mod quakeworld;
+mod valve;
+
+/// Request and response format of a master server protocol
+///
+/// Only [`request`](MasterProtocol::request) and [`response_header`](MasterProtocol::response_header)
+/// are required for protocols that reply with 6 byte address entries (4 byte IPv4 address and
+/// 2 byte big-endian port), as used by QuakeWorld, Quake 2 and many of their derivatives.
+///
+/// # Example
+///
+/// ```
+/// use masterstat::MasterProtocol;
+///
+/// struct Hexen2;
+///
+/// impl MasterProtocol for Hexen2 {
+///     fn request(&self) -> Vec<u8> {
+///         b"c\n".to_vec()
+///     }
+///
+///     fn response_header(&self) -> &[u8] {
+///         &[0xff, 0xff, 0xff, 0xff, 0x64, 0x0a]
+///     }
+/// }
+/// ```
+pub trait MasterProtocol {
+    /// Request sent to the master server
+    fn request(&self) -> Vec<u8>;
+
+    /// Header expected at the start of every response packet
+    fn response_header(&self) -> &[u8];
+
+    /// Check and strip the header of a response packet, returning the packet body
+    fn strip_header<'a>(&self, packet: &'a [u8]) -> Result<&'a [u8]> {
+        packet
+            .strip_prefix(self.response_header())
+            .ok_or_else(|| Error::InvalidHeader {
+                packet: packet.to_vec(),
+            })
+    }
+
+    /// Decode server address entries from a packet body
+    fn decode_entries(&self, body: &[u8]) -> Result<Vec<ServerAddress>> {
+        decode_raw_addresses(body)
+    }
+
+    /// Whether the packet body completes the list, no more packets are read after it
+    fn is_last_packet(&self, _body: &[u8]) -> bool {
+        false
+    }
+
+    /// Request for more entries following the entries of the last packet, for protocols that page
+    fn next_request(&self, _entries: &[ServerAddress]) -> Option<Vec<u8>> {
+        None
+    }
+}
+
+/// Decode a body of consecutive 6 byte address entries (IPv4 address and big-endian port)
+pub fn decode_raw_addresses(body: &[u8]) -> Result<Vec<ServerAddress>> {
+    let remainder_size = body.len() % RAW_ADDRESS_SIZE;
+    if remainder_size > 0 {
+        return Err(Error::TruncatedBody {
+            remainder: body[body.len() - remainder_size..].to_vec(),
+        });
+    }
+
+    let server_addresses = body
+        .chunks_exact(RAW_ADDRESS_SIZE)
+        .filter_map(RawServerAddress::read_from)
+        .map(ServerAddress::from)
+        .collect::<Vec<ServerAddress>>();
+
+    Ok(server_addresses)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_decode_raw_addresses() -> Result<()> {
+        // valid body
+        {
+            let body = [192, 168, 1, 1, 0x75, 0x30, 192, 168, 1, 2, 0x75, 0x30];
+            let result = decode_raw_addresses(&body)?;
+            assert_eq!(
+                result.iter().map(|a| a.to_string()).collect::<Vec<_>>(),
+                vec!["192.168.1.1:30000", "192.168.1.2:30000"]
+            );
+        }
+
+        // truncated body
+        {
+            let body = [192, 168, 1, 1, 0x75, 0x30, 192, 168];
+            let result = decode_raw_addresses(&body);
+            assert!(
+                matches!(result, Err(Error::TruncatedBody { remainder }) if remainder == [192, 168])
+            );
+        }
+
+        Ok(())
+    }
+}
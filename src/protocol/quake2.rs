@@ -0,0 +1,80 @@
+use std::time::Duration;
+
+use crate::command::{server_addresses_async_with, server_addresses_with};
+use crate::error::Result;
+use crate::master_address::MasterAddress;
+use crate::protocol::MasterProtocol;
+use crate::server_address::ServerAddress;
+
+const QUERY_COMMAND: &[u8] = b"query";
+const SERVERS_RESPONSE_HEADER: &[u8] = b"\xff\xff\xff\xffservers ";
+
+/// Quake 2 master server protocol (`query` request, `servers` response)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Quake2;
+
+impl MasterProtocol for Quake2 {
+    fn request(&self) -> Vec<u8> {
+        QUERY_COMMAND.to_vec()
+    }
+
+    fn response_header(&self) -> &[u8] {
+        SERVERS_RESPONSE_HEADER
+    }
+}
+
+/// Get server addresses from a single Quake 2 master server
+///
+/// Same as [`server_addresses_with`](crate::server_addresses_with) with [`Quake2`].
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+///
+/// let master = "master.quakeservers.net:27900";
+/// let timeout = Some(Duration::from_secs(2));
+/// match masterstat::quake2_server_addresses(&master, timeout) {
+///     Ok(addresses) => { println!("found {} server addresses", addresses.len()) },
+///     Err(e) => { eprintln!("error: {}", e); }
+/// }
+/// ```
+pub fn quake2_server_addresses(
+    master_address: impl Into<MasterAddress>,
+    timeout: Option<Duration>,
+) -> Result<Vec<ServerAddress>> {
+    server_addresses_with(master_address, &Quake2, timeout)
+}
+
+/// Get server addresses from a single Quake 2 master server (async)
+///
+/// Same as [`server_addresses_async_with`](crate::server_addresses_async_with) with [`Quake2`].
+pub async fn quake2_server_addresses_async(
+    master_address: impl Into<MasterAddress>,
+    timeout: Option<Duration>,
+) -> Result<Vec<ServerAddress>> {
+    server_addresses_async_with(master_address, &Quake2, timeout).await
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::error::Error;
+
+    use super::*;
+
+    #[test]
+    fn test_strip_header() {
+        assert_eq!(
+            Quake2
+                .strip_header(b"\xff\xff\xff\xffservers \x01\x02")
+                .unwrap(),
+            [1, 2]
+        );
+        assert!(matches!(
+            Quake2.strip_header(&[0xff, 0xff, 0xff, 0xff, 0x64, 0x0a]),
+            Err(Error::InvalidHeader { .. })
+        ));
+    }
+}
@@ -0,0 +1,242 @@
+use std::time::Duration;
+
+use zerocopy::FromBytes;
+
+use crate::command::{server_addresses_async_with, server_addresses_with};
+use crate::error::{Error, Result};
+use crate::master_address::MasterAddress;
+use crate::protocol::MasterProtocol;
+use crate::server_address::{
+    RawServerAddress, RawServerAddressV6, ServerAddress, RAW_ADDRESS_SIZE, RAW_ADDRESS_V6_SIZE,
+};
+
+const GETSERVERS_COMMAND: &[u8] = b"\xff\xff\xff\xffgetservers ";
+const GETSERVERS_RESPONSE_HEADER: &[u8] = b"\xff\xff\xff\xffgetserversResponse";
+const ENTRY_SEPARATOR: u8 = b'\\';
+const ENTRY_SEPARATOR_V6: u8 = b'/';
+/// `EOT` padded to the size of an address entry, as `69.79.84.0:0` it is never a valid server
+const END_OF_TRANSMISSION: &[u8] = b"EOT\0\0\0";
+
+/// Quake 3 (dpmaster) `getservers` protocol
+///
+/// Common protocol numbers are `68` (Quake 3 1.32, ioquake3) and `71` (OpenArena).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Quake3 {
+    /// Game protocol number
+    pub protocol: u32,
+    /// Include servers without players
+    pub empty: bool,
+    /// Include servers that are full
+    pub full: bool,
+}
+
+impl Quake3 {
+    /// Request all servers (empty and full) using the given protocol
+    pub fn new(protocol: u32) -> Self {
+        Quake3 {
+            protocol,
+            empty: true,
+            full: true,
+        }
+    }
+}
+
+impl MasterProtocol for Quake3 {
+    fn request(&self) -> Vec<u8> {
+        let mut request = GETSERVERS_COMMAND.to_vec();
+        request.extend_from_slice(self.protocol.to_string().as_bytes());
+        request.extend_from_slice(&filter_flags(self.empty, self.full));
+        request
+    }
+
+    fn response_header(&self) -> &[u8] {
+        GETSERVERS_RESPONSE_HEADER
+    }
+
+    fn decode_entries(&self, body: &[u8]) -> Result<Vec<ServerAddress>> {
+        decode_getservers_entries(body)
+    }
+
+    fn is_last_packet(&self, body: &[u8]) -> bool {
+        is_last_getservers_packet(body)
+    }
+}
+
+/// Get server addresses from a single Quake 3 (dpmaster) master server
+///
+/// Same as [`server_addresses_with`](crate::server_addresses_with) with [`Quake3`].
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use masterstat::Quake3;
+///
+/// let master = "master.ioquake3.org:27950";
+/// let timeout = Some(Duration::from_secs(2));
+/// match masterstat::quake3_server_addresses(&master, &Quake3::new(68), timeout) {
+///     Ok(addresses) => { println!("found {} server addresses", addresses.len()) },
+///     Err(e) => { eprintln!("error: {}", e); }
+/// }
+/// ```
+pub fn quake3_server_addresses(
+    master_address: impl Into<MasterAddress>,
+    query: &Quake3,
+    timeout: Option<Duration>,
+) -> Result<Vec<ServerAddress>> {
+    server_addresses_with(master_address, query, timeout)
+}
+
+/// Get server addresses from a single Quake 3 (dpmaster) master server (async)
+///
+/// Same as [`server_addresses_async_with`](crate::server_addresses_async_with) with [`Quake3`].
+pub async fn quake3_server_addresses_async(
+    master_address: impl Into<MasterAddress>,
+    query: &Quake3,
+    timeout: Option<Duration>,
+) -> Result<Vec<ServerAddress>> {
+    server_addresses_async_with(master_address, query, timeout).await
+}
+
+/// Optional ` empty` and ` full` request arguments
+pub(crate) fn filter_flags(empty: bool, full: bool) -> Vec<u8> {
+    let mut flags = vec![];
+
+    if empty {
+        flags.extend_from_slice(b" empty");
+    }
+    if full {
+        flags.extend_from_slice(b" full");
+    }
+    flags
+}
+
+/// Whether the body ends with the `\EOT` terminator, sent after the last packet
+pub(crate) fn is_last_getservers_packet(body: &[u8]) -> bool {
+    body.strip_suffix(END_OF_TRANSMISSION)
+        .is_some_and(|rest| rest.ends_with(&[ENTRY_SEPARATOR]))
+}
+
+/// Decode `\`-separated IPv4 and `/`-separated IPv6 address entries
+/// until the `\EOT` terminator (or end of packet)
+pub(crate) fn decode_getservers_entries(body: &[u8]) -> Result<Vec<ServerAddress>> {
+    let mut server_addresses = vec![];
+    let mut rest = body;
+
+    while let Some((&separator, entry)) = rest.split_first() {
+        let entry_size = match separator {
+            ENTRY_SEPARATOR if entry.starts_with(END_OF_TRANSMISSION) => break,
+            ENTRY_SEPARATOR => RAW_ADDRESS_SIZE,
+            ENTRY_SEPARATOR_V6 => RAW_ADDRESS_V6_SIZE,
+            _ => break,
+        };
+
+        if entry.len() < entry_size {
+            return Err(Error::TruncatedBody {
+                remainder: rest.to_vec(),
+            });
+        }
+
+        let (raw_address, remainder) = entry.split_at(entry_size);
+        let server_address = match separator {
+            ENTRY_SEPARATOR => RawServerAddress::read_from(raw_address).map(ServerAddress::from),
+            _ => RawServerAddressV6::read_from(raw_address).map(ServerAddress::from),
+        };
+        server_addresses.extend(server_address);
+        rest = remainder;
+    }
+
+    Ok(server_addresses)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_request() {
+        assert_eq!(
+            Quake3::new(68).request(),
+            b"\xff\xff\xff\xffgetservers 68 empty full".to_vec()
+        );
+        assert_eq!(
+            Quake3 {
+                protocol: 71,
+                empty: false,
+                full: true,
+            }
+            .request(),
+            b"\xff\xff\xff\xffgetservers 71 full".to_vec()
+        );
+    }
+
+    #[test]
+    fn test_is_last_packet() {
+        let protocol = Quake3::new(68);
+        assert!(protocol.is_last_packet(b"\\\xc0\xa8\x01\x01\x6d\x38\\EOT\0\0\0"));
+        assert!(!protocol.is_last_packet(b"\\\xc0\xa8\x01\x01\x6d\x38"));
+        assert!(!protocol.is_last_packet(b"\\\xc0\xa8\x01\x01\x6d\x38EOT\0\0\0"));
+    }
+
+    #[test]
+    fn test_decode_entries() -> Result<()> {
+        // stops at EOT
+        {
+            let body = [
+                &b"\\"[..],
+                &[192, 168, 1, 1, 0x6d, 0x38],
+                b"\\",
+                &[192, 168, 1, 2, 0x6d, 0x38],
+                b"\\EOT\0\0\0",
+                b"\\",
+                &[10, 0, 0, 1, 0x6d, 0x38],
+            ]
+            .concat();
+            let result = Quake3::new(68).decode_entries(&body)?;
+            assert_eq!(
+                result.iter().map(|a| a.to_string()).collect::<Vec<_>>(),
+                vec!["192.168.1.1:27960", "192.168.1.2:27960"]
+            );
+        }
+
+        // server with an address starting with EOT (69.79.84.x)
+        {
+            let body = [
+                &b"\\"[..],
+                &[69, 79, 84, 1, 0x6d, 0x38],
+                b"\\",
+                &[192, 168, 1, 1, 0x6d, 0x38],
+                b"\\EOT\0\0\0",
+            ]
+            .concat();
+            let result = decode_getservers_entries(&body)?;
+            assert_eq!(
+                result.iter().map(|a| a.to_string()).collect::<Vec<_>>(),
+                vec!["69.79.84.1:27960", "192.168.1.1:27960"]
+            );
+        }
+
+        // IPv6 entries
+        {
+            let ipv6_entry = [
+                0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0x6d, 0x38,
+            ];
+            let body = [b"/", &ipv6_entry[..], b"\\EOT\0\0\0"].concat();
+            let result = decode_getservers_entries(&body)?;
+            assert_eq!(result[0].to_string(), "[2001:db8::1]:27960");
+        }
+
+        // truncated entry
+        {
+            let body = [b"\\", &[192, 168, 1][..]].concat();
+            let result = decode_getservers_entries(&body);
+            assert!(
+                matches!(result, Err(Error::TruncatedBody { remainder }) if remainder == [b'\\', 192, 168, 1])
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,47 @@
+use crate::protocol::MasterProtocol;
+
+/// Sent by game servers to be listed (`a`)
+pub(crate) const HEARTBEAT: u8 = b'a';
+/// Sent by game servers to be removed from the list (`C`)
+pub(crate) const SHUTDOWN: u8 = b'C';
+/// Request for the server list (`c`)
+pub(crate) const SERVERS: u8 = b'c';
+pub(crate) const SERVERS_COMMAND: [u8; 3] = [SERVERS, b'\n', 0x00];
+pub(crate) const SERVERS_RESPONSE_HEADER: [u8; 6] = [0xff, 0xff, 0xff, 0xff, 0x64, 0x0a];
+
+/// QuakeWorld master server protocol (`c\n` request, `d\n` response)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct QuakeWorld;
+
+impl MasterProtocol for QuakeWorld {
+    fn request(&self) -> Vec<u8> {
+        SERVERS_COMMAND.to_vec()
+    }
+
+    fn response_header(&self) -> &[u8] {
+        &SERVERS_RESPONSE_HEADER
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::error::Error;
+
+    use super::*;
+
+    #[test]
+    fn test_strip_header() {
+        assert_eq!(
+            QuakeWorld
+                .strip_header(&[0xff, 0xff, 0xff, 0xff, 0x64, 0x0a, 1, 2])
+                .unwrap(),
+            [1, 2]
+        );
+        assert!(matches!(
+            QuakeWorld.strip_header(&[0xff, 0xff]),
+            Err(Error::InvalidHeader { packet }) if packet == [0xff, 0xff]
+        ));
+    }
+}
@@ -0,0 +1,171 @@
+use std::net::{IpAddr, Ipv4Addr};
+use std::time::Duration;
+
+use crate::command::{server_addresses_async_with, server_addresses_with};
+use crate::error::Result;
+use crate::master_address::MasterAddress;
+use crate::protocol::{decode_raw_addresses, MasterProtocol};
+use crate::server_address::{ServerAddress, RAW_ADDRESS_SIZE};
+
+const QUERY_COMMAND: u8 = 0x31;
+const QUERY_RESPONSE_HEADER: &[u8] = &[0xff, 0xff, 0xff, 0xff, 0x66, 0x0a];
+const FIRST_SEED: &str = "0.0.0.0:0";
+
+/// Region codes of the Valve master server protocol
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ValveRegion {
+    UsEastCoast = 0x00,
+    UsWestCoast = 0x01,
+    SouthAmerica = 0x02,
+    Europe = 0x03,
+    Asia = 0x04,
+    Australia = 0x05,
+    MiddleEast = 0x06,
+    Africa = 0x07,
+    World = 0xff,
+}
+
+/// Valve (Source/GoldSrc) master server protocol
+///
+/// Pages through the list, using the last address of each reply as seed of the next request,
+/// until the `0.0.0.0:0` terminator is received.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Valve {
+    pub region: ValveRegion,
+    /// Filter string, e.g. `\gamedir\cstrike\empty\1`
+    pub filter: String,
+}
+
+impl Valve {
+    /// Request servers in all regions matching the given filter
+    pub fn new(filter: &str) -> Self {
+        Valve {
+            region: ValveRegion::World,
+            filter: filter.to_string(),
+        }
+    }
+
+    fn request_from(&self, seed: &str) -> Vec<u8> {
+        let mut request = vec![QUERY_COMMAND, self.region as u8];
+        request.extend_from_slice(seed.as_bytes());
+        request.push(0);
+        request.extend_from_slice(self.filter.as_bytes());
+        request.push(0);
+        request
+    }
+}
+
+impl MasterProtocol for Valve {
+    fn request(&self) -> Vec<u8> {
+        self.request_from(FIRST_SEED)
+    }
+
+    fn response_header(&self) -> &[u8] {
+        QUERY_RESPONSE_HEADER
+    }
+
+    fn decode_entries(&self, body: &[u8]) -> Result<Vec<ServerAddress>> {
+        let server_addresses = decode_raw_addresses(body)?
+            .into_iter()
+            .filter(|a| !is_terminator(a))
+            .collect::<Vec<ServerAddress>>();
+        Ok(server_addresses)
+    }
+
+    fn is_last_packet(&self, body: &[u8]) -> bool {
+        body.len() % RAW_ADDRESS_SIZE == 0 && body.ends_with(&[0; RAW_ADDRESS_SIZE])
+    }
+
+    fn next_request(&self, entries: &[ServerAddress]) -> Option<Vec<u8>> {
+        entries
+            .last()
+            .map(|last| self.request_from(&last.to_string()))
+    }
+}
+
+/// Get server addresses from a single Valve master server
+///
+/// Same as [`server_addresses_with`](crate::server_addresses_with) with [`Valve`].
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use masterstat::Valve;
+///
+/// let master = "hl1master.steampowered.com:27011";
+/// let timeout = Some(Duration::from_secs(2));
+/// match masterstat::valve_server_addresses(&master, &Valve::new("\\gamedir\\cstrike"), timeout) {
+///     Ok(addresses) => { println!("found {} server addresses", addresses.len()) },
+///     Err(e) => { eprintln!("error: {}", e); }
+/// }
+/// ```
+pub fn valve_server_addresses(
+    master_address: impl Into<MasterAddress>,
+    query: &Valve,
+    timeout: Option<Duration>,
+) -> Result<Vec<ServerAddress>> {
+    server_addresses_with(master_address, query, timeout)
+}
+
+/// Get server addresses from a single Valve master server (async)
+///
+/// Same as [`server_addresses_async_with`](crate::server_addresses_async_with) with [`Valve`].
+pub async fn valve_server_addresses_async(
+    master_address: impl Into<MasterAddress>,
+    query: &Valve,
+    timeout: Option<Duration>,
+) -> Result<Vec<ServerAddress>> {
+    server_addresses_async_with(master_address, query, timeout).await
+}
+
+/// `0.0.0.0:0` marks the end of the list
+fn is_terminator(address: &ServerAddress) -> bool {
+    address.ip == IpAddr::V4(Ipv4Addr::UNSPECIFIED) && address.port == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_request() {
+        let protocol = Valve {
+            region: ValveRegion::Europe,
+            filter: "\\gamedir\\cstrike".to_string(),
+        };
+        assert_eq!(
+            protocol.request(),
+            b"\x31\x030.0.0.0:0\0\\gamedir\\cstrike\0".to_vec()
+        );
+    }
+
+    #[test]
+    fn test_paging() -> Result<()> {
+        let protocol = Valve::new("");
+
+        // page
+        {
+            let body = [10, 0, 0, 2, 0x69, 0x87, 10, 0, 0, 1, 0x69, 0x87];
+            let entries = protocol.decode_entries(&body)?;
+            assert_eq!(entries.len(), 2);
+            assert!(!protocol.is_last_packet(&body));
+            assert_eq!(
+                protocol.next_request(&entries),
+                Some(b"\x31\xff10.0.0.1:27015\0\0".to_vec())
+            );
+        }
+
+        // last page
+        {
+            let body = [10, 0, 0, 3, 0x69, 0x87, 0, 0, 0, 0, 0, 0];
+            let entries = protocol.decode_entries(&body)?;
+            assert_eq!(entries.len(), 1);
+            assert!(protocol.is_last_packet(&body));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,203 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::cancel::{CancellationToken, Stop};
+use crate::retry::RetryPolicy;
+use crate::shared_socket::SharedSocket;
+
+/// Time to wait for more packets once a response has started arriving
+pub const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Size of the receive buffer, the largest packet that can be read
+pub const DEFAULT_BUFFER_SIZE: usize = 16 * 1024; // 16 kb
+
+/// Which response packets are accepted
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SourceAddressPolicy {
+    /// Only packets from the address the request was sent to
+    #[default]
+    Exact,
+    /// Packets from any port of the IP the request was sent to,
+    /// for masters replying from a different port
+    SameIp,
+    /// Packets from any address, for masters behind load balancers (not supported by
+    /// [`MasterClient`](crate::MasterClient))
+    Any,
+}
+
+/// Options for querying master servers
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use masterstat::{QueryOptions, QuakeWorld};
+///
+/// async fn test() {
+///     let masters = ["master.quakeworld.nu:27000", "master.quakeservers.net:27000"];
+///     let options = QueryOptions::new()
+///         .timeout(Duration::from_secs(2))
+///         .resolve_timeout(Duration::from_millis(500))
+///         .retries(2)
+///         .concurrency(8);
+///     let server_addresses = masterstat::server_addresses_from_many_with_options(&masters, &QuakeWorld, &options).await;
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct QueryOptions {
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) deadline: Option<Duration>,
+    pub(crate) cancellation_token: Option<CancellationToken>,
+    pub(crate) resolve_timeout: Option<Duration>,
+    pub(crate) idle_timeout: Duration,
+    pub(crate) buffer_size: usize,
+    pub(crate) retry: RetryPolicy,
+    pub(crate) bind_address: Option<SocketAddr>,
+    pub(crate) concurrency: Option<usize>,
+    pub(crate) stagger: Duration,
+    pub(crate) source_address_policy: SourceAddressPolicy,
+    /// Socket of a [`MasterClient`](crate::MasterClient), instead of a socket per query
+    pub(crate) shared_socket: Option<Arc<SharedSocket>>,
+}
+
+impl Default for QueryOptions {
+    fn default() -> Self {
+        QueryOptions {
+            timeout: None,
+            deadline: None,
+            cancellation_token: None,
+            resolve_timeout: None,
+            idle_timeout: DEFAULT_IDLE_TIMEOUT,
+            buffer_size: DEFAULT_BUFFER_SIZE,
+            retry: RetryPolicy::default(),
+            bind_address: None,
+            concurrency: None,
+            stagger: Duration::ZERO,
+            source_address_policy: SourceAddressPolicy::default(),
+            shared_socket: None,
+        }
+    }
+}
+
+impl QueryOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Overall time for querying a master, including all response packets (default: no limit)
+    pub fn timeout(mut self, timeout: impl Into<Option<Duration>>) -> Self {
+        self.timeout = timeout.into();
+        self
+    }
+
+    /// Hard limit for the whole call, including resolving and retries of all masters (default: no limit)
+    ///
+    /// Masters that have not finished by then are returned with the addresses received so far
+    /// and `complete: false`, or fail with [`Error::Timeout`](crate::Error::Timeout) if nothing
+    /// was received. Results of the other masters are returned as usual. Blocking queries limit
+    /// each attempt to the remaining time.
+    pub fn deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Token for cancelling the call (default: none)
+    ///
+    /// Masters that have not finished when the token is cancelled are stopped like at the
+    /// deadline, failing with [`Error::Cancelled`](crate::Error::Cancelled) if nothing was
+    /// received. Blocking queries check the token before every attempt.
+    pub fn cancellation_token(mut self, token: CancellationToken) -> Self {
+        self.cancellation_token = Some(token);
+        self
+    }
+
+    /// Time for resolving the master address (default: no limit)
+    ///
+    /// Only applies to async queries, blocking queries use the system resolver as is.
+    pub fn resolve_timeout(mut self, resolve_timeout: Duration) -> Self {
+        self.resolve_timeout = Some(resolve_timeout);
+        self
+    }
+
+    /// Time to wait for more packets once a response has started arriving (default: 500 ms)
+    ///
+    /// Zero stops reading after the first packet of each response.
+    pub fn idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.idle_timeout = idle_timeout;
+        self
+    }
+
+    /// Size of the receive buffer, larger packets are truncated (default: 16 kb)
+    pub fn buffer_size(mut self, buffer_size: usize) -> Self {
+        self.buffer_size = buffer_size;
+        self
+    }
+
+    /// Number of extra attempts after a failed attempt (default: 0)
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retry.retries = retries;
+        self
+    }
+
+    /// Retries, backoff and redundant requests (default: [`RetryPolicy::default`])
+    pub fn retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Local address to send from (default: any address of the same family as the master)
+    pub fn bind_address(mut self, bind_address: SocketAddr) -> Self {
+        self.bind_address = Some(bind_address);
+        self
+    }
+
+    /// Maximum number of masters (or servers) queried at the same time (default: no limit)
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = Some(concurrency.max(1));
+        self
+    }
+
+    /// Minimum time between starting queries to different masters (or servers) (default: none)
+    pub fn stagger(mut self, stagger: Duration) -> Self {
+        self.stagger = stagger;
+        self
+    }
+
+    /// Which response packets are accepted (default: [`SourceAddressPolicy::Exact`])
+    pub fn source_address_policy(mut self, policy: SourceAddressPolicy) -> Self {
+        self.source_address_policy = policy;
+        self
+    }
+
+    /// Deadline and cancellation, starting now
+    pub(crate) fn stop(&self) -> Stop {
+        Stop::new(self.deadline, self.cancellation_token.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_builder() {
+        let options = QueryOptions::new()
+            .timeout(Duration::from_secs(1))
+            .retries(3)
+            .concurrency(0)
+            .stagger(Duration::from_millis(10))
+            .source_address_policy(SourceAddressPolicy::Any);
+
+        assert_eq!(options.timeout, Some(Duration::from_secs(1)));
+        assert_eq!(options.retry.retries, 3);
+        assert_eq!(options.concurrency, Some(1));
+        assert_eq!(options.stagger, Duration::from_millis(10));
+        assert_eq!(options.source_address_policy, SourceAddressPolicy::Any);
+        assert_eq!(options.buffer_size, DEFAULT_BUFFER_SIZE);
+        assert_eq!(QueryOptions::new().timeout(None).timeout, None);
+    }
+}
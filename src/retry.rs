@@ -0,0 +1,94 @@
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::time::{Duration, Instant};
+
+/// How master queries are repeated to compensate for lost UDP packets
+///
+/// The default policy sends the request once and does not retry.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RetryPolicy {
+    /// Number of extra attempts after a failed attempt
+    pub retries: u32,
+    /// Delay before the first retry, doubled for every following retry
+    pub backoff: Duration,
+    /// Upper limit of the delay between attempts
+    pub max_backoff: Duration,
+    /// Randomize delays (between half and all of the delay) to spread out retries
+    pub jitter: bool,
+    /// Number of requests sent per attempt, the addresses of all responses are merged
+    ///
+    /// Only applies to async queries, blocking queries send one request per attempt.
+    /// Each request is sent from its own socket, a [`MasterClient`](crate::MasterClient)
+    /// sends all of them at once from its shared socket.
+    pub redundancy: u32,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            retries: 0,
+            backoff: Duration::from_millis(250),
+            max_backoff: Duration::from_secs(2),
+            jitter: true,
+            redundancy: 1,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before the given retry (1 for the first retry)
+    pub fn delay(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+        let delay = self.backoff.saturating_mul(factor).min(self.max_backoff);
+
+        match self.jitter {
+            true => delay.mul_f64(0.5 + random_fraction() / 2.0),
+            false => delay,
+        }
+    }
+}
+
+/// Random number in `[0, 1)`, good enough for jitter
+fn random_fraction() -> f64 {
+    let random = RandomState::new().hash_one(Instant::now());
+    (random >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_delay() {
+        // exponential, capped
+        {
+            let policy = RetryPolicy {
+                backoff: Duration::from_millis(100),
+                max_backoff: Duration::from_millis(500),
+                jitter: false,
+                ..Default::default()
+            };
+            let delays = (1..=5).map(|r| policy.delay(r)).collect::<Vec<Duration>>();
+            assert_eq!(
+                delays,
+                [100, 200, 400, 500, 500]
+                    .map(Duration::from_millis)
+                    .to_vec()
+            );
+        }
+
+        // jitter
+        {
+            let policy = RetryPolicy {
+                backoff: Duration::from_millis(100),
+                ..Default::default()
+            };
+            for _ in 0..100 {
+                let delay = policy.delay(1);
+                assert!(delay >= Duration::from_millis(50) && delay <= Duration::from_millis(100));
+            }
+        }
+    }
+}
@@ -0,0 +1,106 @@
+//! Serialize [`ServerAddress`] as an `"ip:port"` string instead of a struct
+//!
+//! Use with `#[serde(with = "masterstat::serde_string")]` on a `ServerAddress` field,
+//! or `#[serde(with = "masterstat::serde_string::vec")]` on a `Vec<ServerAddress>` field.
+//!
+//! # Example
+//!
+//! ```
+//! use masterstat::ServerAddress;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Server {
+//!     #[serde(with = "masterstat::serde_string")]
+//!     address: ServerAddress,
+//! }
+//! ```
+
+use serde::{Deserialize, Deserializer, Serializer};
+
+use crate::server_address::ServerAddress;
+
+pub fn serialize<S: Serializer>(address: &ServerAddress, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(address)
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<ServerAddress, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    parse(&value).map_err(serde::de::Error::custom)
+}
+
+fn parse(value: &str) -> Result<ServerAddress, String> {
+    value
+        .parse()
+        .map_err(|e| format!("invalid server address {}: {}", value, e))
+}
+
+/// Serialize `Vec<ServerAddress>` as an array of `"ip:port"` strings
+pub mod vec {
+    use serde::ser::SerializeSeq;
+
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        addresses: &[ServerAddress],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(addresses.len()))?;
+        for address in addresses {
+            seq.serialize_element(&address.to_string())?;
+        }
+        seq.end()
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<ServerAddress>, D::Error> {
+        Vec::<String>::deserialize(deserializer)?
+            .iter()
+            .map(|value| parse(value).map_err(serde::de::Error::custom))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde::Serialize;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Servers {
+        #[serde(with = "crate::serde_string")]
+        primary: ServerAddress,
+        #[serde(with = "crate::serde_string::vec")]
+        all: Vec<ServerAddress>,
+    }
+
+    #[test]
+    fn test_serde_string() -> anyhow::Result<()> {
+        let servers = Servers {
+            primary: ServerAddress::new("10.0.0.1".parse()?, 27500),
+            all: vec![
+                ServerAddress::new("10.0.0.1".parse()?, 27500),
+                ServerAddress::new("::1".parse()?, 27960),
+            ],
+        };
+        let json = r#"{"primary":"10.0.0.1:27500","all":["10.0.0.1:27500","[::1]:27960"]}"#;
+
+        assert_eq!(serde_json::to_string(&servers)?, json);
+        assert_eq!(serde_json::from_str::<Servers>(json)?, servers);
+        assert!(serde_json::from_str::<Servers>(r#"{"primary":"foo","all":[]}"#).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_struct_form() -> anyhow::Result<()> {
+        let address = ServerAddress::new("10.0.0.1".parse()?, 27500);
+        let json = r#"{"ip":"10.0.0.1","port":27500}"#;
+
+        assert_eq!(serde_json::to_string(&address)?, json);
+        assert_eq!(serde_json::from_str::<ServerAddress>(json)?, address);
+        Ok(())
+    }
+}
@@ -1,9 +1,15 @@
 use std::fmt::Display;
+use std::net::{
+    AddrParseError, IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6,
+    ToSocketAddrs,
+};
+use std::str::FromStr;
 
 use zerocopy::{BigEndian, U16};
 use zerocopy_derive::{FromBytes, FromZeroes};
 
 pub const RAW_ADDRESS_SIZE: usize = 6;
+pub const RAW_ADDRESS_V6_SIZE: usize = 18;
 
 #[derive(FromZeroes, FromBytes)]
 pub struct RawServerAddress {
@@ -11,22 +17,107 @@ pub struct RawServerAddress {
     pub port: U16<BigEndian>,
 }
 
+#[derive(FromZeroes, FromBytes)]
+pub struct RawServerAddressV6 {
+    pub ip: [u8; 16],
+    pub port: U16<BigEndian>,
+}
+
+/// Address of a game server
+///
+/// Ordering is numeric (by IP, then port), with IPv4 addresses before IPv6 addresses.
 #[derive(Clone, Debug, Hash, PartialOrd, Ord, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ServerAddress {
-    pub ip: String,
+    pub ip: IpAddr,
     pub port: u16,
 }
 
+impl ServerAddress {
+    pub fn new(ip: IpAddr, port: u16) -> Self {
+        ServerAddress { ip, port }
+    }
+
+    pub fn socket_addr(&self) -> SocketAddr {
+        SocketAddr::new(self.ip, self.port)
+    }
+}
+
+/// Formats as `ip:port`, IPv6 addresses are enclosed in brackets (`[ip]:port`)
 impl Display for ServerAddress {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}:{}", self.ip, self.port)
+        write!(f, "{}", self.socket_addr())
+    }
+}
+
+/// Parses `ip:port`, IPv6 addresses must be enclosed in brackets (`[ip]:port`)
+impl FromStr for ServerAddress {
+    type Err = AddrParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(s.parse::<SocketAddr>()?.into())
+    }
+}
+
+impl TryFrom<&str> for ServerAddress {
+    type Error = AddrParseError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<SocketAddr> for ServerAddress {
+    fn from(address: SocketAddr) -> Self {
+        ServerAddress::new(address.ip(), address.port())
+    }
+}
+
+impl From<SocketAddrV4> for ServerAddress {
+    fn from(address: SocketAddrV4) -> Self {
+        SocketAddr::V4(address).into()
+    }
+}
+
+impl From<SocketAddrV6> for ServerAddress {
+    fn from(address: SocketAddrV6) -> Self {
+        SocketAddr::V6(address).into()
+    }
+}
+
+impl From<ServerAddress> for SocketAddr {
+    fn from(address: ServerAddress) -> Self {
+        address.socket_addr()
+    }
+}
+
+impl From<&ServerAddress> for SocketAddr {
+    fn from(address: &ServerAddress) -> Self {
+        address.socket_addr()
+    }
+}
+
+impl ToSocketAddrs for ServerAddress {
+    type Iter = std::option::IntoIter<SocketAddr>;
+
+    fn to_socket_addrs(&self) -> std::io::Result<Self::Iter> {
+        Ok(Some(self.socket_addr()).into_iter())
     }
 }
 
 impl From<RawServerAddress> for ServerAddress {
     fn from(raw: RawServerAddress) -> Self {
         ServerAddress {
-            ip: raw.ip.map(|b| b.to_string()).join("."),
+            ip: IpAddr::V4(Ipv4Addr::from(raw.ip)),
+            port: raw.port.into(),
+        }
+    }
+}
+
+impl From<RawServerAddressV6> for ServerAddress {
+    fn from(raw: RawServerAddressV6) -> Self {
+        ServerAddress {
+            ip: IpAddr::V6(Ipv6Addr::from(raw.ip)),
             port: raw.port.into(),
         }
     }
@@ -37,7 +128,7 @@ mod tests {
     use pretty_assertions::assert_eq;
     use zerocopy::{FromBytes, U16};
 
-    use crate::server_address::{RawServerAddress, ServerAddress};
+    use super::*;
 
     #[test]
     fn test_raw_server_address() {
@@ -53,16 +144,84 @@ mod tests {
             port: U16::from(30000),
         };
         let address = ServerAddress::from(raw_address);
-        assert_eq!(address.ip, "192.168.1.1");
+        assert_eq!(address.ip, Ipv4Addr::new(192, 168, 1, 1));
+        assert_eq!(address.port, 30000);
+    }
+
+    #[test]
+    fn test_server_address_from_raw_server_address_v6() {
+        let mut bytes = [0; RAW_ADDRESS_V6_SIZE];
+        bytes[15] = 1;
+        bytes[16..].copy_from_slice(&[0x75, 0x30]);
+        let raw_address = RawServerAddressV6::read_from(&bytes).unwrap();
+        let address = ServerAddress::from(raw_address);
+        assert_eq!(address.ip, Ipv6Addr::LOCALHOST);
         assert_eq!(address.port, 30000);
     }
 
     #[test]
     fn test_server_address_display() {
-        let address = ServerAddress {
-            ip: "192.168.1.1".to_string(),
-            port: 30000,
-        };
+        let address = ServerAddress::new(Ipv4Addr::new(192, 168, 1, 1).into(), 30000);
         assert_eq!(address.to_string(), "192.168.1.1:30000");
+
+        let address = ServerAddress::new(Ipv6Addr::LOCALHOST.into(), 30000);
+        assert_eq!(address.to_string(), "[::1]:30000");
+    }
+
+    #[test]
+    fn test_server_address_ordering() {
+        let mut addresses = [
+            ServerAddress::new(Ipv6Addr::LOCALHOST.into(), 1),
+            ServerAddress::new(Ipv4Addr::new(192, 168, 1, 10).into(), 1),
+            ServerAddress::new(Ipv4Addr::new(192, 168, 1, 2).into(), 2),
+            ServerAddress::new(Ipv4Addr::new(192, 168, 1, 2).into(), 1),
+        ];
+        addresses.sort();
+        assert_eq!(
+            addresses.iter().map(|a| a.to_string()).collect::<Vec<_>>(),
+            vec![
+                "192.168.1.2:1",
+                "192.168.1.2:2",
+                "192.168.1.10:1",
+                "[::1]:1"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!(
+            "10.0.0.1:27500".parse::<ServerAddress>(),
+            Ok(ServerAddress::new("10.0.0.1".parse().unwrap(), 27500))
+        );
+        assert_eq!(
+            ServerAddress::try_from("[::1]:27960"),
+            Ok(ServerAddress::new("::1".parse().unwrap(), 27960))
+        );
+        assert!("10.0.0.1".parse::<ServerAddress>().is_err());
+        assert!("foo:27500".parse::<ServerAddress>().is_err());
+        assert!("10.0.0.1:99999".parse::<ServerAddress>().is_err());
+    }
+
+    #[test]
+    fn test_socket_addr_conversions() {
+        let socket_address: SocketAddr = "10.0.0.1:27500".parse().unwrap();
+        let address = ServerAddress::from(socket_address);
+        assert_eq!(SocketAddr::from(&address), socket_address);
+        assert_eq!(
+            ServerAddress::from(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 1)),
+            ServerAddress::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 1)
+        );
+        assert_eq!(
+            ServerAddress::from(SocketAddrV6::new(Ipv6Addr::LOCALHOST, 1, 0, 0)),
+            ServerAddress::new(IpAddr::V6(Ipv6Addr::LOCALHOST), 1)
+        );
+        assert_eq!(
+            address
+                .to_socket_addrs()
+                .unwrap()
+                .collect::<Vec<SocketAddr>>(),
+            vec![socket_address]
+        );
     }
 }
@@ -0,0 +1,559 @@
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::error::{Error, Result};
+use crate::query_options::QueryOptions;
+use crate::server_address::ServerAddress;
+use crate::stagger::query_each;
+use crate::udp;
+use crate::udp::Next;
+
+/// Request server info, players, spectators and teams
+const STATUS_COMMAND: &[u8] = b"\xff\xff\xff\xffstatus 23\n";
+const STATUS_RESPONSE_HEADER: &[u8] = b"\xff\xff\xff\xffn";
+const SPECTATOR_NAME_PREFIX: &[u8] = b"\\s\\";
+const SPECTATOR_FRAGS: i32 = -9999;
+
+/// Server info of a QuakeWorld server
+///
+/// Text is converted from the Quake character set to readable ASCII.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ServerInfo {
+    pub hostname: String,
+    pub map: String,
+    pub maxclients: u32,
+    /// Value of `*gamedir`, `qw` if not set
+    pub gamedir: String,
+    /// All server info keys and values, including the ones above
+    pub settings: BTreeMap<String, String>,
+}
+
+/// Player (or spectator) on a QuakeWorld server
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Player {
+    pub userid: u32,
+    pub frags: i32,
+    /// Minutes on the server
+    pub time: u32,
+    pub ping: u32,
+    pub name: String,
+    pub skin: String,
+    pub top_color: u8,
+    pub bottom_color: u8,
+    pub team: String,
+    pub is_spectator: bool,
+}
+
+/// Status of a QuakeWorld server
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ServerStatus {
+    pub info: ServerInfo,
+    pub players: Vec<Player>,
+    /// Player lines that could not be parsed and are not included in `players`
+    pub invalid_player_lines: Vec<String>,
+}
+
+/// Outcome of querying the status of a single server
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ServerStatusResult {
+    pub address: ServerAddress,
+    pub outcome: Result<ServerStatus>,
+    /// Time until the response arrived
+    pub rtt: Option<Duration>,
+}
+
+impl ServerStatusResult {
+    /// Result of a server that did not reply
+    pub(crate) fn failed(address: &ServerAddress, error: Error) -> Self {
+        ServerStatusResult {
+            address: address.clone(),
+            outcome: Err(error),
+            rtt: None,
+        }
+    }
+}
+
+/// Get status (server info and players) of a QuakeWorld server
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use masterstat::ServerAddress;
+///
+/// let address = ServerAddress::new("127.0.0.1".parse().unwrap(), 27500);
+/// let timeout = Some(Duration::from_secs(2));
+/// match masterstat::server_status(&address, timeout) {
+///     Ok(status) => { println!("{} ({} players)", status.info.hostname, status.players.len()) },
+///     Err(e) => { eprintln!("error: {}", e); }
+/// }
+/// ```
+pub fn server_status(address: &ServerAddress, timeout: Option<Duration>) -> Result<ServerStatus> {
+    server_status_with_options(address, &QueryOptions::new().timeout(timeout))
+}
+
+/// Get status (server info and players) of a QuakeWorld server using the given options
+///
+/// The timeout is limited to the remaining time until the deadline,
+/// cancellation is checked before the request is sent.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use masterstat::{QueryOptions, ServerAddress};
+///
+/// let address = ServerAddress::new("127.0.0.1".parse().unwrap(), 27500);
+/// let options = QueryOptions::new().timeout(Duration::from_secs(2)).idle_timeout(Duration::ZERO);
+/// let status = masterstat::server_status_with_options(&address, &options);
+/// ```
+pub fn server_status_with_options(
+    address: &ServerAddress,
+    options: &QueryOptions,
+) -> Result<ServerStatus> {
+    let stop = options.stop();
+    if let Some(reason) = stop.reason() {
+        return Err(reason);
+    }
+
+    let options = &options.clone().timeout(stop.limit(options.timeout));
+    let response = udp::exchange(address.socket_addr(), STATUS_COMMAND, options, |_| {
+        Next::Done
+    })?;
+    parse_status_response(&response.packets[0])
+}
+
+/// Get status (server info and players) of a QuakeWorld server (async)
+pub async fn server_status_async(
+    address: &ServerAddress,
+    timeout: Option<Duration>,
+) -> Result<ServerStatus> {
+    server_status_async_with_options(address, &QueryOptions::new().timeout(timeout)).await
+}
+
+/// Get status (server info and players) of a QuakeWorld server using the given options (async)
+pub async fn server_status_async_with_options(
+    address: &ServerAddress,
+    options: &QueryOptions,
+) -> Result<ServerStatus> {
+    options
+        .stop()
+        .run(server_status_result(address, options))
+        .await
+        .and_then(|result| result.outcome)
+}
+
+/// Get status of many QuakeWorld servers, one result per server (async, in parallel)
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+///
+/// async fn test() {
+///     let masters = ["master.quakeworld.nu:27000", "master.quakeservers.net:27000"];
+///     let timeout = Some(Duration::from_secs(2));
+///     let server_addresses = masterstat::server_addresses_from_many(&masters, timeout).await;
+///
+///     for result in masterstat::server_status_from_many(&server_addresses, timeout).await {
+///         if let Ok(status) = result.outcome {
+///             println!("{}: {} on {}", result.address, status.info.hostname, status.info.map);
+///         }
+///     }
+/// }
+/// ```
+pub async fn server_status_from_many(
+    addresses: &[ServerAddress],
+    timeout: Option<Duration>,
+) -> Vec<ServerStatusResult> {
+    let options = QueryOptions::new().timeout(timeout);
+    server_status_from_many_with_options(addresses, &options).await
+}
+
+/// Get status of many QuakeWorld servers using the given options, one result per server
+/// (async, in parallel)
+///
+/// Use [`QueryOptions::concurrency`] and [`QueryOptions::stagger`] to limit the number of open
+/// sockets and the rate of requests. Servers that have not replied when the deadline passes or
+/// the query is cancelled fail with [`Error::Timeout`] or [`Error::Cancelled`].
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use masterstat::{QueryOptions, ServerAddress};
+///
+/// async fn test(server_addresses: &[ServerAddress]) {
+///     let options = QueryOptions::new()
+///         .timeout(Duration::from_secs(1))
+///         .concurrency(64)
+///         .stagger(Duration::from_millis(5));
+///     let results = masterstat::server_status_from_many_with_options(server_addresses, &options).await;
+/// }
+/// ```
+pub async fn server_status_from_many_with_options(
+    addresses: &[ServerAddress],
+    options: &QueryOptions,
+) -> Vec<ServerStatusResult> {
+    let stop = options.stop();
+    query_each(addresses, options, &stop, |address| async {
+        stop.run(server_status_result(address, options))
+            .await
+            .unwrap_or_else(|reason| ServerStatusResult::failed(address, reason))
+    })
+    .await
+}
+
+pub(crate) async fn server_status_result(
+    address: &ServerAddress,
+    options: &QueryOptions,
+) -> ServerStatusResult {
+    let response = udp::exchange_async(address.socket_addr(), STATUS_COMMAND, options, |_| {
+        Next::Done
+    })
+    .await;
+
+    let (outcome, rtt) = match response {
+        Ok(response) => (
+            parse_status_response(&response.packets[0]),
+            Some(response.rtt),
+        ),
+        Err(e) => (Err(e), None),
+    };
+
+    ServerStatusResult {
+        address: address.clone(),
+        outcome,
+        rtt,
+    }
+}
+
+fn parse_status_response(packet: &[u8]) -> Result<ServerStatus> {
+    let Some(body) = packet.strip_prefix(STATUS_RESPONSE_HEADER) else {
+        return Err(Error::InvalidHeader {
+            packet: packet.to_vec(),
+        });
+    };
+
+    let body = body.strip_suffix(b"\0").unwrap_or(body);
+    let mut lines = body.split(|b| *b == b'\n').filter(|l| !l.is_empty());
+    let info = parse_server_info(lines.next().unwrap_or_default());
+    let mut players = vec![];
+    let mut invalid_player_lines = vec![];
+
+    for line in lines {
+        match parse_player(line) {
+            Ok(player) => players.push(player),
+            Err(_) => invalid_player_lines.push(String::from_utf8_lossy(line).to_string()),
+        }
+    }
+
+    Ok(ServerStatus {
+        info,
+        players,
+        invalid_player_lines,
+    })
+}
+
+/// Parse `\key\value\key\value` server info string
+fn parse_server_info(info_string: &[u8]) -> ServerInfo {
+    let info_string = info_string.strip_prefix(b"\\").unwrap_or(info_string);
+    let parts = info_string
+        .split(|b| *b == b'\\')
+        .map(quake_text)
+        .collect::<Vec<String>>();
+    let settings = parts
+        .chunks_exact(2)
+        .map(|kv| (kv[0].clone(), kv[1].clone()))
+        .collect::<BTreeMap<String, String>>();
+
+    let value = |key: &str| settings.get(key).cloned().unwrap_or_default();
+
+    ServerInfo {
+        hostname: value("hostname"),
+        map: value("map"),
+        maxclients: value("maxclients").parse().unwrap_or_default(),
+        gamedir: settings
+            .get("*gamedir")
+            .cloned()
+            .unwrap_or("qw".to_string()),
+        settings,
+    }
+}
+
+/// Parse `userid frags time ping "name" "skin" top_color bottom_color ["team"]`
+///
+/// Spectators have frags `S` (or `-9999`) and/or a `\s\` name prefix.
+fn parse_player(line: &[u8]) -> Result<Player> {
+    let invalid = || Error::InvalidEntry {
+        entry: line.to_vec(),
+    };
+    let tokens = tokenize(line);
+
+    if tokens.len() < 8 {
+        return Err(invalid());
+    }
+
+    fn number<T: FromStr>(token: &[u8]) -> Option<T> {
+        std::str::from_utf8(token).ok()?.parse().ok()
+    }
+
+    let (frags, spectator_frags) = match tokens[1] {
+        b"S" => (0, true),
+        token => {
+            let frags = number(token).ok_or_else(invalid)?;
+            (frags, frags == SPECTATOR_FRAGS)
+        }
+    };
+    let (name, spectator_name) = match tokens[4].strip_prefix(SPECTATOR_NAME_PREFIX) {
+        Some(name) => (name, true),
+        None => (tokens[4], false),
+    };
+
+    Ok(Player {
+        userid: number(tokens[0]).ok_or_else(invalid)?,
+        frags,
+        time: number(tokens[2]).ok_or_else(invalid)?,
+        ping: number(tokens[3]).ok_or_else(invalid)?,
+        name: quake_text(name),
+        skin: quake_text(tokens[5]),
+        top_color: number(tokens[6]).ok_or_else(invalid)?,
+        bottom_color: number(tokens[7]).ok_or_else(invalid)?,
+        team: tokens.get(8).map(|t| quake_text(t)).unwrap_or_default(),
+        is_spectator: spectator_frags || spectator_name,
+    })
+}
+
+/// Split on spaces, keeping quoted tokens (without quotes) intact
+fn tokenize(line: &[u8]) -> Vec<&[u8]> {
+    let mut tokens = vec![];
+    let mut rest = line;
+
+    loop {
+        let start = rest.iter().position(|b| *b != b' ').unwrap_or(rest.len());
+        rest = &rest[start..];
+
+        let (token, remainder) = match rest.first() {
+            None => break,
+            Some(b'"') => {
+                let quoted = &rest[1..];
+                let end = quoted
+                    .iter()
+                    .position(|b| *b == b'"')
+                    .unwrap_or(quoted.len());
+                (&quoted[..end], &quoted[(end + 1).min(quoted.len())..])
+            }
+            Some(_) => {
+                let end = rest.iter().position(|b| *b == b' ').unwrap_or(rest.len());
+                rest.split_at(end)
+            }
+        };
+        tokens.push(token);
+        rest = remainder;
+    }
+
+    tokens
+}
+
+/// Convert text in the Quake character set to readable ASCII
+fn quake_text(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|b| match b & 0x7f {
+            0x10 => '[',
+            0x11 => ']',
+            c @ 0x12..=0x1b => (b'0' + c - 0x12) as char,
+            0x1c => '.',
+            c if c < 0x20 || c == 0x7f => '_',
+            c => c as char,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::test_util::{spawn_handler, spawn_responder};
+
+    use super::*;
+
+    const RESPONSE: &[u8] =
+        b"\xff\xff\xff\xffn\\maxclients\\8\\map\\dm2\\hostname\\QUAKE.SE KTX:28501\\*gamedir\\qw\n\
+        12 31 47 25 \"XantoM\" \"\" 4 4 \"f0m\"\n\
+        13 S 5 12 \"\\s\\[ServeMe]\" \"\" 12 11 \"lqwc\"\n";
+
+    #[test]
+    fn test_parse_status_response() -> Result<()> {
+        // invalid header
+        {
+            let result = parse_status_response(b"\xff\xff\xff\xffc\n");
+            assert!(matches!(result, Err(Error::InvalidHeader { .. })));
+        }
+
+        // valid response
+        {
+            let status = parse_status_response(RESPONSE)?;
+            assert_eq!(status.info.hostname, "QUAKE.SE KTX:28501");
+            assert_eq!(status.info.map, "dm2");
+            assert_eq!(status.info.maxclients, 8);
+            assert_eq!(status.info.gamedir, "qw");
+            assert_eq!(status.info.settings.len(), 4);
+            assert_eq!(
+                status.players,
+                vec![
+                    Player {
+                        userid: 12,
+                        frags: 31,
+                        time: 47,
+                        ping: 25,
+                        name: "XantoM".to_string(),
+                        skin: "".to_string(),
+                        top_color: 4,
+                        bottom_color: 4,
+                        team: "f0m".to_string(),
+                        is_spectator: false,
+                    },
+                    Player {
+                        userid: 13,
+                        frags: 0,
+                        time: 5,
+                        ping: 12,
+                        name: "[ServeMe]".to_string(),
+                        skin: "".to_string(),
+                        top_color: 12,
+                        bottom_color: 11,
+                        team: "lqwc".to_string(),
+                        is_spectator: true,
+                    },
+                ]
+            );
+        }
+
+        // invalid player lines are skipped
+        {
+            let status = parse_status_response(
+                b"\xff\xff\xff\xffn\\map\\dm2\n12 x\n12 31 47 25 \"a\" \"\" 4 4\n",
+            )?;
+            assert_eq!(status.players.len(), 1);
+            assert_eq!(status.invalid_player_lines, vec!["12 x".to_string()]);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_player() {
+        // valid
+        assert_eq!(
+            parse_player(b"1 -5 2 3 \"a\" \"b\" 4 13").unwrap().frags,
+            -5
+        );
+
+        // too few tokens
+        assert!(matches!(
+            parse_player(b"12 x"),
+            Err(Error::InvalidEntry { entry }) if entry == b"12 x"
+        ));
+
+        // out of range numbers are not wrapped
+        assert!(parse_player(b"1 0 2 3 \"a\" \"b\" 256 4").is_err());
+        assert!(parse_player(b"-1 0 2 3 \"a\" \"b\" 4 4").is_err());
+        assert!(parse_player(b"1 4294967296 2 3 \"a\" \"b\" 4 4").is_err());
+    }
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            tokenize(b"1  -2 \"a b\" \"\" c"),
+            vec![&b"1"[..], b"-2", b"a b", b"", b"c"]
+        );
+    }
+
+    #[test]
+    fn test_quake_text() {
+        assert_eq!(quake_text(b"\x10\x12\x1b\x11 \xe1\xf8\xe5"), "[09] axe");
+    }
+
+    #[tokio::test]
+    async fn test_server_status_with_options() {
+        let server = spawn_handler(|_| vec![RESPONSE.to_vec()]);
+        let address = ServerAddress::new(server.ip(), server.port());
+        let options = QueryOptions::new().timeout(Duration::from_secs(1));
+
+        // async
+        let status = server_status_async_with_options(&address, &options).await;
+        assert_eq!(status.unwrap().info.map, "dm2");
+
+        // blocking
+        let status = tokio::task::spawn_blocking({
+            let (address, options) = (address.clone(), options.clone());
+            move || server_status_with_options(&address, &options)
+        })
+        .await
+        .unwrap();
+        assert_eq!(status.unwrap().info.map, "dm2");
+
+        // deadline
+        let silent = spawn_handler(|_| vec![]);
+        let address = ServerAddress::new(silent.ip(), silent.port());
+        let options = QueryOptions::new().deadline(Duration::from_millis(100));
+        let status = server_status_async_with_options(&address, &options).await;
+        assert!(matches!(status, Err(Error::Timeout)));
+        let status =
+            tokio::task::spawn_blocking(move || server_status_with_options(&address, &options))
+                .await
+                .unwrap();
+        assert!(matches!(status, Err(Error::Timeout)));
+    }
+
+    #[tokio::test]
+    async fn test_server_status_from_many() {
+        let server = spawn_responder(vec![RESPONSE.to_vec()]);
+        let silent = spawn_responder(vec![]);
+        let addresses = [
+            ServerAddress::new(server.ip(), server.port()),
+            ServerAddress::new(silent.ip(), silent.port()),
+        ];
+        let results = server_status_from_many(&addresses, Some(Duration::from_millis(200))).await;
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].address, addresses[0]);
+        assert_eq!(results[0].outcome.as_ref().unwrap().info.map, "dm2");
+        assert!(results[0].rtt.is_some());
+        assert!(matches!(results[1].outcome, Err(Error::Timeout)));
+    }
+
+    #[tokio::test]
+    async fn test_server_status_from_many_with_options() {
+        let addresses = (0..4)
+            .map(|_| spawn_responder(vec![RESPONSE.to_vec()]))
+            .map(|server| ServerAddress::new(server.ip(), server.port()))
+            .collect::<Vec<ServerAddress>>();
+        let options = QueryOptions::new()
+            .timeout(Duration::from_secs(1))
+            .concurrency(2)
+            .stagger(Duration::from_millis(50));
+        let started = std::time::Instant::now();
+        let results = server_status_from_many_with_options(&addresses, &options).await;
+
+        assert!(started.elapsed() >= Duration::from_millis(150));
+        assert_eq!(
+            results.iter().map(|r| &r.address).collect::<Vec<_>>(),
+            addresses.iter().collect::<Vec<_>>()
+        );
+        assert!(results.iter().all(|r| r.outcome.is_ok()));
+
+        // deadline
+        let silent = spawn_responder(vec![]);
+        let addresses = [ServerAddress::new(silent.ip(), silent.port())];
+        let options = QueryOptions::new().deadline(Duration::from_millis(100));
+        let results = server_status_from_many_with_options(&addresses, &options).await;
+        assert!(matches!(results[0].outcome, Err(Error::Timeout)));
+    }
+}
@@ -0,0 +1,229 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::io;
+use std::net::SocketAddr;
+use std::pin::pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::net::UdpSocket;
+use tokio::sync::{mpsc, Notify};
+use tokio::task::JoinHandle;
+
+use crate::query_options::SourceAddressPolicy;
+use crate::udp;
+
+type Packet = (Vec<u8>, SocketAddr);
+
+/// Packets queued per route, further packets are dropped until the exchange catches up
+const ROUTE_CAPACITY: usize = 64;
+
+/// Pause after a receive error that is not transient, instead of retrying right away
+const READ_ERROR_BACKOFF: Duration = Duration::from_millis(100);
+
+/// UDP socket shared by many exchanges, replies are routed to the exchange by source address
+///
+/// Only one exchange per peer address can be active at a time, others wait for their turn.
+#[derive(Debug)]
+pub(crate) struct SharedSocket {
+    socket: Arc<UdpSocket>,
+    routes: Arc<Routes>,
+    reader: JoinHandle<()>,
+}
+
+#[derive(Debug, Default)]
+struct Routes {
+    table: Mutex<HashMap<SocketAddr, (SourceAddressPolicy, mpsc::Sender<Packet>)>>,
+    released: Notify,
+}
+
+impl Routes {
+    /// Route for a packet from `source`, an exact match or a route accepting other ports
+    fn find(&self, source: &SocketAddr) -> Option<mpsc::Sender<Packet>> {
+        let table = self.table.lock().unwrap();
+
+        if let Some((_, sender)) = table.get(source) {
+            return Some(sender.clone());
+        }
+
+        table
+            .iter()
+            .find(|(address, (policy, _))| {
+                *policy != SourceAddressPolicy::Exact && address.ip() == source.ip()
+            })
+            .map(|(_, (_, sender))| sender.clone())
+    }
+}
+
+impl SharedSocket {
+    /// Bind the socket and start routing received packets (requires a tokio runtime)
+    pub async fn bind(address: SocketAddr, buffer_size: usize) -> io::Result<Self> {
+        let socket = Arc::new(UdpSocket::bind(address).await?);
+        let routes = Arc::<Routes>::default();
+
+        let reader = tokio::spawn({
+            let (socket, routes) = (socket.clone(), routes.clone());
+
+            async move {
+                let mut buffer = vec![0; buffer_size];
+
+                loop {
+                    let (bytes_read, source) = match socket.recv_from(&mut buffer).await {
+                        Ok(received) => received,
+                        Err(e) if udp::is_transient(&e) => continue,
+                        Err(_) => {
+                            tokio::time::sleep(READ_ERROR_BACKOFF).await;
+                            continue;
+                        }
+                    };
+                    if let Some(sender) = routes.find(&source) {
+                        let _ = sender.try_send((buffer[..bytes_read].to_vec(), source));
+                    }
+                }
+            }
+        });
+
+        Ok(SharedSocket {
+            socket,
+            routes,
+            reader,
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    /// Receive packets from `address`, waits while another exchange with the same address is active
+    pub async fn route(&self, address: SocketAddr, policy: SourceAddressPolicy) -> Route<'_> {
+        loop {
+            let mut released = pin!(self.routes.released.notified());
+            released.as_mut().enable();
+
+            if let Entry::Vacant(entry) = self.routes.table.lock().unwrap().entry(address) {
+                let (sender, receiver) = mpsc::channel(ROUTE_CAPACITY);
+                entry.insert((policy, sender));
+                return Route {
+                    shared: self,
+                    address,
+                    receiver,
+                };
+            }
+
+            released.await;
+        }
+    }
+}
+
+impl Drop for SharedSocket {
+    fn drop(&mut self) {
+        self.reader.abort();
+    }
+}
+
+/// Packets from one peer address, the route is released when dropped
+pub(crate) struct Route<'a> {
+    shared: &'a SharedSocket,
+    address: SocketAddr,
+    receiver: mpsc::Receiver<Packet>,
+}
+
+impl Route<'_> {
+    pub async fn send_to(&self, message: &[u8], address: SocketAddr) -> io::Result<usize> {
+        self.shared.socket.send_to(message, address).await
+    }
+
+    /// Receive the next packet, truncated to the size of the buffer
+    pub async fn recv_from(&mut self, buffer: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        let Some((packet, source)) = self.receiver.recv().await else {
+            return Err(io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "shared socket closed",
+            ));
+        };
+
+        let bytes_read = packet.len().min(buffer.len());
+        buffer[..bytes_read].copy_from_slice(&packet[..bytes_read]);
+        Ok((bytes_read, source))
+    }
+}
+
+impl Drop for Route<'_> {
+    fn drop(&mut self) {
+        self.shared
+            .routes
+            .table
+            .lock()
+            .unwrap()
+            .remove(&self.address);
+        self.shared.routes.released.notify_waiters();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::test_util::spawn_handler;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_route() {
+        let shared = SharedSocket::bind("127.0.0.1:0".parse().unwrap(), 1024)
+            .await
+            .unwrap();
+        let first = spawn_handler(|_| vec![b"first".to_vec()]);
+        let second = spawn_handler(|_| vec![b"second".to_vec()]);
+        let mut buffer = [0; 1024];
+
+        // by source address
+        {
+            let mut first_route = shared.route(first, SourceAddressPolicy::Exact).await;
+            let mut second_route = shared.route(second, SourceAddressPolicy::Exact).await;
+            second_route.send_to(b"ping", second).await.unwrap();
+            first_route.send_to(b"ping", first).await.unwrap();
+
+            let (bytes_read, source) = first_route.recv_from(&mut buffer).await.unwrap();
+            assert_eq!((&buffer[..bytes_read], source), (&b"first"[..], first));
+            let (bytes_read, source) = second_route.recv_from(&mut buffer).await.unwrap();
+            assert_eq!((&buffer[..bytes_read], source), (&b"second"[..], second));
+        }
+
+        // one route per address at a time
+        {
+            let route = shared.route(first, SourceAddressPolicy::Exact).await;
+            let waiting = tokio::time::timeout(
+                Duration::from_millis(50),
+                shared.route(first, SourceAddressPolicy::Exact),
+            )
+            .await;
+            assert!(waiting.is_err());
+
+            drop(route);
+            let released = tokio::time::timeout(
+                Duration::from_millis(50),
+                shared.route(first, SourceAddressPolicy::Exact),
+            )
+            .await;
+            assert!(released.is_ok());
+        }
+
+        // packets beyond the capacity of a route are dropped
+        {
+            let flooding = spawn_handler(|_| vec![b"flood".to_vec(); ROUTE_CAPACITY * 2]);
+            let mut route = shared.route(flooding, SourceAddressPolicy::Exact).await;
+            route.send_to(b"ping", flooding).await.unwrap();
+            tokio::time::sleep(Duration::from_millis(100)).await;
+
+            let mut received = 0;
+            while tokio::time::timeout(Duration::from_millis(20), route.recv_from(&mut buffer))
+                .await
+                .is_ok()
+            {
+                received += 1;
+            }
+            assert_eq!(received, ROUTE_CAPACITY);
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use futures::StreamExt;
+
+use crate::cancel::Stop;
+use crate::query_options::QueryOptions;
+use crate::server_address::ServerAddress;
+
+/// Spaces out the start of queries so that bursts of requests don't trip rate limits
+#[derive(Debug, Default)]
+pub(crate) struct Stagger {
+    interval: Duration,
+    next_start: Mutex<Option<Instant>>,
+}
+
+impl Stagger {
+    /// At least `interval` between starts, zero to start right away
+    pub fn new(interval: Duration) -> Self {
+        Stagger {
+            interval,
+            next_start: Mutex::new(None),
+        }
+    }
+
+    /// Wait for the next free start time
+    pub async fn wait(&self) {
+        if self.interval.is_zero() {
+            return;
+        }
+
+        let start = {
+            let mut next_start = self.next_start.lock().unwrap();
+            let now = Instant::now();
+            let start = next_start.map_or(now, |next_start| next_start.max(now));
+            *next_start = Some(start + self.interval);
+            start
+        };
+
+        tokio::time::sleep_until(start.into()).await;
+    }
+}
+
+/// Query many servers, results are in the same order as the addresses
+///
+/// At most [`QueryOptions::concurrency`] queries run at a time, started at least
+/// [`QueryOptions::stagger`] apart. Once `stop` is reached queries start right away,
+/// `query` is expected to give up by itself.
+pub(crate) async fn query_each<'a, T, F: Future<Output = T>>(
+    addresses: &'a [ServerAddress],
+    options: &QueryOptions,
+    stop: &Stop,
+    query: impl Fn(&'a ServerAddress) -> F,
+) -> Vec<T> {
+    let stagger = Stagger::new(options.stagger);
+    futures::stream::iter(addresses)
+        .map(|address| {
+            let (stagger, query) = (&stagger, &query);
+            async move {
+                let _ = stop.run(stagger.wait()).await;
+                query(address).await
+            }
+        })
+        .buffered(options.concurrency.unwrap_or(addresses.len()).max(1))
+        .collect()
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_wait() {
+        // spaced out
+        {
+            let stagger = Stagger::new(Duration::from_millis(50));
+            let started = Instant::now();
+            futures::future::join_all((0..3).map(|_| stagger.wait())).await;
+            assert!(started.elapsed() >= Duration::from_millis(100));
+        }
+
+        // zero
+        {
+            let stagger = Stagger::new(Duration::ZERO);
+            let started = Instant::now();
+            futures::future::join_all((0..100).map(|_| stagger.wait())).await;
+            assert!(started.elapsed() < Duration::from_millis(50));
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use std::net::{SocketAddr, UdpSocket};
+use std::thread;
+
+/// Spawn a UDP peer that answers the first message it receives with the given packets
+pub fn spawn_responder(packets: Vec<Vec<u8>>) -> SocketAddr {
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let address = socket.local_addr().unwrap();
+
+    thread::spawn(move || {
+        let mut buffer = [0; 64];
+        let (_, peer) = socket.recv_from(&mut buffer).unwrap();
+        for packet in packets {
+            socket.send_to(&packet, peer).unwrap();
+        }
+    });
+
+    address
+}
+
+/// Spawn a UDP peer that answers every message it receives with the packets returned by `handler`
+pub fn spawn_handler(
+    mut handler: impl FnMut(&[u8]) -> Vec<Vec<u8>> + Send + 'static,
+) -> SocketAddr {
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let address = socket.local_addr().unwrap();
+
+    thread::spawn(move || {
+        let mut buffer = [0; 1024];
+        while let Ok((bytes_read, peer)) = socket.recv_from(&mut buffer) {
+            for packet in handler(&buffer[..bytes_read]) {
+                socket.send_to(&packet, peer).unwrap();
+            }
+        }
+    });
+
+    address
+}
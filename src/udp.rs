@@ -0,0 +1,443 @@
+use std::io;
+use std::io::ErrorKind;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::{Duration, Instant};
+
+use crate::error::{Error, Result};
+use crate::query_options::{QueryOptions, SourceAddressPolicy};
+use crate::shared_socket::Route;
+
+/// Packets received in response to a message
+pub struct Response {
+    pub packets: Vec<Vec<u8>>,
+    pub rtt: Duration,
+}
+
+/// Resolve an address, preferring IPv4
+pub fn resolve(address: impl ToSocketAddrs) -> Result<SocketAddr> {
+    let addresses = address.to_socket_addrs().map_err(Error::Resolve)?;
+    pick_address(addresses.collect())
+}
+
+/// Resolve an address, preferring IPv4 (async)
+pub async fn resolve_async(address: impl tokio::net::ToSocketAddrs) -> Result<SocketAddr> {
+    let addresses = tokio::net::lookup_host(address)
+        .await
+        .map_err(Error::Resolve)?;
+    pick_address(addresses.collect())
+}
+
+/// Resolve an address within the given time, preferring IPv4 (async)
+pub async fn resolve_async_within(
+    address: impl tokio::net::ToSocketAddrs,
+    timeout: Option<Duration>,
+) -> Result<SocketAddr> {
+    let Some(timeout) = timeout else {
+        return resolve_async(address).await;
+    };
+
+    match tokio::time::timeout(timeout, resolve_async(address)).await {
+        Ok(result) => result,
+        Err(_) => Err(Error::Resolve(io::Error::new(
+            ErrorKind::TimedOut,
+            "timed out resolving address",
+        ))),
+    }
+}
+
+fn pick_address(addresses: Vec<SocketAddr>) -> Result<SocketAddr> {
+    addresses
+        .iter()
+        .find(|a| a.is_ipv4())
+        .or(addresses.first())
+        .copied()
+        .ok_or_else(|| Error::Resolve(io::Error::new(ErrorKind::NotFound, "no address found")))
+}
+
+/// Whether a receive error only concerns a single packet and the socket can still be used
+///
+/// E.g. Windows reports an ICMP port unreachable for an earlier send as a connection reset.
+pub fn is_transient(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        ErrorKind::ConnectionReset
+            | ErrorKind::ConnectionRefused
+            | ErrorKind::ConnectionAborted
+            | ErrorKind::Interrupted
+            | ErrorKind::WouldBlock
+            | ErrorKind::TimedOut
+    )
+}
+
+/// Local address to send from, by default unspecified and of the same family as the given address
+fn bind_address(address: &SocketAddr, options: &QueryOptions) -> SocketAddr {
+    match (options.bind_address, address) {
+        (Some(bind_address), _) => bind_address,
+        (None, SocketAddr::V4(_)) => (Ipv4Addr::UNSPECIFIED, 0).into(),
+        (None, SocketAddr::V6(_)) => (Ipv6Addr::UNSPECIFIED, 0).into(),
+    }
+}
+
+/// Whether a packet from `source` is accepted as response from `address`
+fn is_accepted(source: &SocketAddr, address: &SocketAddr, policy: SourceAddressPolicy) -> bool {
+    match policy {
+        SourceAddressPolicy::Exact => source == address,
+        SourceAddressPolicy::SameIp => source.ip() == address.ip(),
+        SourceAddressPolicy::Any => true,
+    }
+}
+
+/// What to do after a response packet has been received
+pub enum Next {
+    /// Keep reading packets until the peer goes quiet
+    Read,
+    /// Send a follow-up message and wait for its response
+    Send(Vec<u8>),
+    /// Stop reading
+    Done,
+}
+
+/// Send a message and read response packets, letting `on_packet` decide how to continue
+///
+/// Reading stops once the peer has been quiet for the idle timeout or the timeout expires.
+pub fn exchange(
+    address: SocketAddr,
+    message: &[u8],
+    options: &QueryOptions,
+    mut on_packet: impl FnMut(&[u8]) -> Next,
+) -> Result<Response> {
+    let socket = UdpSocket::bind(bind_address(&address, options))?;
+    let is_connected = options.source_address_policy == SourceAddressPolicy::Exact;
+    if is_connected {
+        socket.connect(address)?;
+    }
+    // connected sockets must not be given an address on BSD and macOS (EISCONN)
+    let send = |message: &[u8]| match is_connected {
+        true => socket.send(message),
+        false => socket.send_to(message, address),
+    };
+    send(message)?;
+
+    let sent_at = Instant::now();
+    let deadline = options.timeout.map(|t| sent_at + t);
+    let mut rtt = Duration::ZERO;
+    let mut buffer = vec![0; options.buffer_size];
+    let mut packets = vec![];
+    let mut awaiting_reply = true;
+
+    while let Some(read_timeout) = next_read_timeout(deadline, awaiting_reply, options) {
+        socket.set_read_timeout(read_timeout)?;
+
+        let (bytes_read, source) = match socket.recv_from(&mut buffer) {
+            Ok(received) => received,
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => break,
+            Err(e) => return Err(Error::Io(e)),
+        };
+        if !is_accepted(&source, &address, options.source_address_policy) {
+            continue;
+        }
+
+        if packets.is_empty() {
+            rtt = sent_at.elapsed();
+        }
+        let packet = &buffer[..bytes_read];
+        packets.push(packet.to_vec());
+        awaiting_reply = false;
+
+        match on_packet(packet) {
+            Next::Read => {}
+            Next::Send(message) => {
+                send(&message)?;
+                awaiting_reply = true;
+            }
+            Next::Done => break,
+        }
+    }
+
+    if packets.is_empty() {
+        return Err(Error::Timeout);
+    }
+
+    Ok(Response { packets, rtt })
+}
+
+/// Send a message and read response packets, letting `on_packet` decide how to continue (async)
+pub async fn exchange_async(
+    address: SocketAddr,
+    message: &[u8],
+    options: &QueryOptions,
+    on_packet: impl FnMut(&[u8]) -> Next,
+) -> Result<Response> {
+    exchange_copies_async(address, message, 1, options, on_packet).await
+}
+
+/// Send `copies` of a message and read the response packets to all of them,
+/// letting `on_packet` decide how to continue (async)
+pub async fn exchange_copies_async(
+    address: SocketAddr,
+    message: &[u8],
+    copies: u32,
+    options: &QueryOptions,
+    mut on_packet: impl FnMut(&[u8]) -> Next,
+) -> Result<Response> {
+    let mut socket = match &options.shared_socket {
+        Some(shared) => {
+            Endpoint::Shared(shared.route(address, options.source_address_policy).await)
+        }
+        None => {
+            let socket = tokio::net::UdpSocket::bind(bind_address(&address, options)).await?;
+            let is_connected = options.source_address_policy == SourceAddressPolicy::Exact;
+            if is_connected {
+                socket.connect(address).await?;
+            }
+            Endpoint::Own {
+                socket,
+                is_connected,
+            }
+        }
+    };
+    for _ in 0..copies.max(1) {
+        socket.send_to(message, address).await?;
+    }
+
+    let sent_at = Instant::now();
+    let deadline = options.timeout.map(|t| sent_at + t);
+    let mut rtt = Duration::ZERO;
+    let mut buffer = vec![0; options.buffer_size];
+    let mut packets = vec![];
+    let mut awaiting_reply = true;
+
+    while let Some(read_timeout) = next_read_timeout(deadline, awaiting_reply, options) {
+        let (bytes_read, source) = match read_timeout {
+            Some(read_timeout) => {
+                match tokio::time::timeout(read_timeout, socket.recv_from(&mut buffer)).await {
+                    Ok(result) => result?,
+                    Err(_) => break,
+                }
+            }
+            None => socket.recv_from(&mut buffer).await?,
+        };
+        if !is_accepted(&source, &address, options.source_address_policy) {
+            continue;
+        }
+
+        if packets.is_empty() {
+            rtt = sent_at.elapsed();
+        }
+        let packet = &buffer[..bytes_read];
+        packets.push(packet.to_vec());
+        awaiting_reply = false;
+
+        match on_packet(packet) {
+            Next::Read => {}
+            Next::Send(message) => {
+                socket.send_to(&message, address).await?;
+                awaiting_reply = true;
+            }
+            Next::Done => break,
+        }
+    }
+
+    if packets.is_empty() {
+        return Err(Error::Timeout);
+    }
+
+    Ok(Response { packets, rtt })
+}
+
+/// Socket of an async exchange, its own or a route on a shared socket
+enum Endpoint<'a> {
+    Own {
+        socket: tokio::net::UdpSocket,
+        is_connected: bool,
+    },
+    Shared(Route<'a>),
+}
+
+impl Endpoint<'_> {
+    /// Send to `address`, using `send` if the socket is connected
+    async fn send_to(&self, message: &[u8], address: SocketAddr) -> io::Result<usize> {
+        match self {
+            Endpoint::Own {
+                socket,
+                is_connected: true,
+            } => socket.send(message).await,
+            Endpoint::Own { socket, .. } => socket.send_to(message, address).await,
+            Endpoint::Shared(route) => route.send_to(message, address).await,
+        }
+    }
+
+    async fn recv_from(&mut self, buffer: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        match self {
+            Endpoint::Own { socket, .. } => socket.recv_from(buffer).await,
+            Endpoint::Shared(route) => route.recv_from(buffer).await,
+        }
+    }
+}
+
+/// Timeout for the next read, or `None` once there is no time left to read
+///
+/// While awaiting a reply the whole remaining time is used,
+/// otherwise the read stops once the peer has been quiet for the idle timeout.
+fn next_read_timeout(
+    deadline: Option<Instant>,
+    awaiting_reply: bool,
+    options: &QueryOptions,
+) -> Option<Option<Duration>> {
+    let remaining = deadline.map(|d| d.saturating_duration_since(Instant::now()));
+
+    let read_timeout = match awaiting_reply {
+        true => remaining,
+        false => Some(remaining.map_or(options.idle_timeout, |r| r.min(options.idle_timeout))),
+    };
+
+    // a zero read timeout is rejected by blocking sockets, there is nothing left to wait for
+    match read_timeout {
+        Some(Duration::ZERO) => None,
+        read_timeout => Some(read_timeout),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::test_util::spawn_responder;
+
+    use super::*;
+
+    #[test]
+    fn test_is_transient() {
+        assert!(is_transient(&io::Error::from(ErrorKind::ConnectionReset)));
+        assert!(!is_transient(&io::Error::from(ErrorKind::InvalidInput)));
+    }
+
+    #[test]
+    fn test_resolve() -> anyhow::Result<()> {
+        assert_eq!(resolve("127.0.0.1:27000")?, "127.0.0.1:27000".parse()?);
+        assert_eq!(resolve("[::1]:27000")?, "[::1]:27000".parse()?);
+        assert!(matches!(resolve("127.0.0.1"), Err(Error::Resolve(_))));
+        Ok(())
+    }
+
+    #[test]
+    fn test_exchange() -> Result<()> {
+        // multiple packets
+        {
+            let address = spawn_responder(vec![vec![1, 2], vec![3], vec![4, 5, 6]]);
+            let response = exchange(
+                address,
+                b"hello",
+                &QueryOptions::new().timeout(Duration::from_secs(2)),
+                |_| Next::Read,
+            )?;
+            assert_eq!(response.packets, vec![vec![1, 2], vec![3], vec![4, 5, 6]]);
+        }
+
+        // no response
+        {
+            let address = spawn_responder(vec![]);
+            let result = exchange(
+                address,
+                b"hello",
+                &QueryOptions::new().timeout(Duration::from_millis(50)),
+                |_| Next::Read,
+            );
+            assert!(matches!(result, Err(Error::Timeout)));
+        }
+
+        // zero idle timeout, first packet only
+        {
+            let address = spawn_responder(vec![vec![1, 2], vec![3]]);
+            let response = exchange(
+                address,
+                b"hello",
+                &QueryOptions::new()
+                    .timeout(Duration::from_secs(2))
+                    .idle_timeout(Duration::ZERO),
+                |_| Next::Read,
+            )?;
+            assert_eq!(response.packets, vec![vec![1, 2]]);
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_exchange_async() -> Result<()> {
+        // multiple packets
+        {
+            let address = spawn_responder(vec![vec![1, 2], vec![3], vec![4, 5, 6]]);
+            let response = exchange_async(
+                address,
+                b"hello",
+                &QueryOptions::new().timeout(Duration::from_secs(2)),
+                |_| Next::Read,
+            )
+            .await?;
+            assert_eq!(response.packets, vec![vec![1, 2], vec![3], vec![4, 5, 6]]);
+        }
+
+        // no response
+        {
+            let address = spawn_responder(vec![]);
+            let result = exchange_async(
+                address,
+                b"hello",
+                &QueryOptions::new().timeout(Duration::from_millis(50)),
+                |_| Next::Read,
+            )
+            .await;
+            assert!(matches!(result, Err(Error::Timeout)));
+        }
+
+        // zero idle timeout, first packet only
+        {
+            let address = spawn_responder(vec![vec![1, 2], vec![3]]);
+            let response = exchange_async(
+                address,
+                b"hello",
+                &QueryOptions::new()
+                    .timeout(Duration::from_secs(2))
+                    .idle_timeout(Duration::ZERO),
+                |_| Next::Read,
+            )
+            .await?;
+            assert_eq!(response.packets, vec![vec![1, 2]]);
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_source_address_policy() -> Result<()> {
+        // replies from another port than the request was sent to
+        let target = std::net::UdpSocket::bind("127.0.0.1:0")?;
+        let address = target.local_addr()?;
+        std::thread::spawn(move || {
+            let replier = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+            let mut buffer = [0; 64];
+            while let Ok((_, source)) = target.recv_from(&mut buffer) {
+                replier.send_to(b"reply", source).unwrap();
+            }
+        });
+        let options = QueryOptions::new().timeout(Duration::from_millis(200));
+
+        // exact
+        {
+            let result = exchange_async(address, b"hello", &options, |_| Next::Done).await;
+            assert!(matches!(result, Err(Error::Timeout)));
+        }
+
+        // same ip
+        {
+            let options = options
+                .clone()
+                .source_address_policy(SourceAddressPolicy::SameIp);
+            let response = exchange_async(address, b"hello", &options, |_| Next::Done).await?;
+            assert_eq!(response.packets, vec![b"reply".to_vec()]);
+        }
+
+        Ok(())
+    }
+}